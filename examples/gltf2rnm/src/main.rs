@@ -1,71 +1,428 @@
 fn main() {
     let (document, buffers, images) = gltf::import("model.gltf").unwrap();
 
+    let mut scene = rnm_3d::SceneBuilder::new();
+
     let images = images
         .into_iter()
-        .filter_map(|image| match image.format {
-            gltf::image::Format::R8G8B8 | gltf::image::Format::R8G8B8A8 => {
-                rnm_3d::Image::from_raw(image.width, image.height, &image.pixels).ok()
-            }
-            _ => None,
+        .map(|image| {
+            load_image(&image).and_then(|image| {
+                scene
+                    .add_image(image)
+                    .map_err(|e| eprintln!("[ERROR]: {e}!"))
+                    .ok()
+            })
         })
         .collect::<Vec<_>>();
+    let texture = |t: gltf::Texture, tex_coord: u32, transform: Option<&gltf::json::Value>| {
+        texture_ref(&images, t, tex_coord, transform)
+    };
 
-    let materials: Vec<rnm_3d::Material> = document
+    let materials = document
         .materials()
         .map(|material| {
-            let albedo = material
-                .pbr_metallic_roughness()
-                .base_color_texture()
-                .map(|t| u8::try_from(t.texture().source().index()).ok())
-                .flatten()
-                .map(|t| rnm_3d::Uniform::Texture(t))
-                .unwrap_or({
-                    let color = material.pbr_metallic_roughness().base_color_factor();
-                    let color = [color[0], color[1], color[2]];
-                    rnm_3d::Uniform::Color(color)
-                });
-            let mre = material
-                .pbr_metallic_roughness()
-                .metallic_roughness_texture()
-                .map(|t| u8::try_from(t.texture().source().index()).ok())
-                .flatten()
-                .map(|t| rnm_3d::Mre::Texture(t))
-                .unwrap_or({
-                    let metallic = material.pbr_metallic_roughness().metallic_factor();
-                    let roughness = material.pbr_metallic_roughness().roughness_factor();
-                    let emission = material.emissive_factor();
-                    let emission = emission[0].max(emission[1]).max(emission[2]);
-                    rnm_3d::Mre::Value {
-                        metallic,
-                        roughness,
-                        emission,
-                    }
-                });
-            let normal = material
-                .normal_texture()
-                .map(|t| u8::try_from(t.texture().source().index()).ok())
-                .flatten();
-            rnm_3d::Material::new(albedo, mre, normal, 0.5)
-        })
-        .collect();
-
-    let meshes: Option<Vec<rnm_3d::Mesh>> = document
-        .meshes()
-        .map(|mesh| {
-            Vec::from_iter(
-                mesh.primitives()
-                    .map(|primitive| load_mesh(primitive, &buffers)),
-            )
+            let pbr = material.pbr_metallic_roughness();
+            let mut builder = rnm_3d::MaterialBuilder::new()
+                .albedo_color(pbr.base_color_factor())
+                .metallic(pbr.metallic_factor())
+                .roughness(pbr.roughness_factor())
+                .emissive(material.emissive_factor())
+                .emissive_strength(material.emissive_strength().unwrap_or(1.0))
+                .alpha_mode(match material.alpha_mode() {
+                    gltf::material::AlphaMode::Opaque => rnm_3d::AlphaMode::Opaque,
+                    gltf::material::AlphaMode::Mask => rnm_3d::AlphaMode::Mask {
+                        cutoff: material.alpha_cutoff().unwrap_or(0.5),
+                    },
+                    gltf::material::AlphaMode::Blend => rnm_3d::AlphaMode::Blend,
+                })
+                .double_sided(material.double_sided());
+            if let Some(t) = pbr.base_color_texture().and_then(|t| {
+                texture(
+                    t.texture(),
+                    t.tex_coord(),
+                    t.extension_value("KHR_texture_transform"),
+                )
+            }) {
+                builder = builder.albedo_texture(t);
+            }
+            if let Some(t) = pbr.metallic_roughness_texture().and_then(|t| {
+                texture(
+                    t.texture(),
+                    t.tex_coord(),
+                    t.extension_value("KHR_texture_transform"),
+                )
+            }) {
+                builder = builder.metallic_roughness_texture(t);
+            }
+            if let Some(normal) = material.normal_texture() {
+                if let Some(t) = texture(
+                    normal.texture(),
+                    normal.tex_coord(),
+                    normal.extension_value("KHR_texture_transform"),
+                ) {
+                    builder = builder.normal_texture(t).normal_scale(normal.scale());
+                }
+            }
+            if let Some(occlusion) = material.occlusion_texture() {
+                if let Some(t) = texture(
+                    occlusion.texture(),
+                    occlusion.tex_coord(),
+                    occlusion.extension_value("KHR_texture_transform"),
+                ) {
+                    builder = builder
+                        .occlusion_texture(t)
+                        .occlusion_strength(occlusion.strength());
+                }
+            }
+            if let Some(t) = material.emissive_texture().and_then(|t| {
+                texture(
+                    t.texture(),
+                    t.tex_coord(),
+                    t.extension_value("KHR_texture_transform"),
+                )
+            }) {
+                builder = builder.emissive_texture(t);
+            }
+            for layer in layers(&material, &document, &images) {
+                builder = builder.layer(layer);
+            }
+            scene
+                .add_material(builder)
+                .map_err(|e| eprintln!("[ERROR]: {e}!"))
+                .ok()
         })
-        .flatten()
-        .collect();
+        .collect::<Vec<_>>();
+
+    for mesh in document.meshes() {
+        for primitive in mesh.primitives() {
+            let material = primitive
+                .material()
+                .index()
+                .and_then(|x| materials.get(x).copied().flatten());
+            if let Some(mesh) = load_mesh(primitive, &buffers, material) {
+                if let Err(e) = scene.add_mesh(mesh) {
+                    eprintln!("[ERROR]: {e}!");
+                }
+            }
+        }
+    }
 
-    let scene = rnm_3d::Scene::new(meshes, Some(materials), None, Some(images)).unwrap();
+    let roots = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .into_iter()
+        .flat_map(|s| s.nodes());
+    for node in roots {
+        load_nodes(node, IDENTITY, &mut scene);
+    }
+
+    let mut scene = scene.build();
+    scene.generate_mips(rnm_3d::MipFilter::Kaiser).unwrap();
     scene.save("model.rnm").unwrap();
 }
 
-fn load_mesh(mesh: gltf::Primitive, buffers: &[gltf::buffer::Data]) -> Option<rnm_3d::Mesh> {
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// column-major like glTF
+fn mul(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut c = [[0.0; 4]; 4];
+    for (j, col) in c.iter_mut().enumerate() {
+        for (i, x) in col.iter_mut().enumerate() {
+            *x = (0..4).map(|k| a[k][i] * b[j][k]).sum();
+        }
+    }
+    c
+}
+
+// rotation of a world matrix with its scale removed, as (x, y, z, w)
+fn rotation(m: &[[f32; 4]; 4]) -> [f32; 4] {
+    let scale =
+        [0, 1, 2].map(|c| (m[c][0] * m[c][0] + m[c][1] * m[c][1] + m[c][2] * m[c][2]).sqrt());
+    let r = |row: usize, col: usize| m[col][row] / scale[col];
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            (r(2, 1) - r(1, 2)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+            (r(1, 0) - r(0, 1)) / s,
+            0.25 * s,
+        ]
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+        [
+            0.25 * s,
+            (r(0, 1) + r(1, 0)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+            (r(2, 1) - r(1, 2)) / s,
+        ]
+    } else if r(1, 1) > r(2, 2) {
+        let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+        [
+            (r(0, 1) + r(1, 0)) / s,
+            0.25 * s,
+            (r(1, 2) + r(2, 1)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+        ]
+    } else {
+        let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+        [
+            (r(0, 2) + r(2, 0)) / s,
+            (r(1, 2) + r(2, 1)) / s,
+            0.25 * s,
+            (r(1, 0) - r(0, 1)) / s,
+        ]
+    }
+}
+
+// cameras and KHR_lights_punctual lights, placed with their world transform
+fn load_nodes(node: gltf::Node, parent: [[f32; 4]; 4], scene: &mut rnm_3d::SceneBuilder) {
+    let world = mul(&parent, &node.transform().matrix());
+    if let Some(camera) = node.camera() {
+        let projection = match camera.projection() {
+            gltf::camera::Projection::Perspective(p) => rnm_3d::Projection::Perspective {
+                yfov: p.yfov(),
+                aspect: p.aspect_ratio(),
+                znear: p.znear(),
+                zfar: p.zfar(),
+            },
+            gltf::camera::Projection::Orthographic(o) => rnm_3d::Projection::Orthographic {
+                xmag: o.xmag(),
+                ymag: o.ymag(),
+                znear: o.znear(),
+                zfar: o.zfar(),
+            },
+        };
+        scene.add_camera(
+            rnm_3d::Camera::new(projection)
+                .with_position([world[3][0], world[3][1], world[3][2]])
+                .with_rotation(rotation(&world)),
+        );
+    }
+    if let Some(light) = node.light() {
+        let position = [world[3][0], world[3][1], world[3][2]];
+        // lights shine down their local -Z
+        let z = [world[2][0], world[2][1], world[2][2]];
+        let len = (z[0] * z[0] + z[1] * z[1] + z[2] * z[2]).sqrt();
+        let direction = z.map(|x| -x / len);
+        // glTF gives candela for point and spot lights and lux for directional ones
+        let (color, power) = (light.color(), light.intensity());
+        let light = match light.kind() {
+            gltf::khr_lights_punctual::Kind::Directional => rnm_3d::Light::Sun {
+                direction,
+                color,
+                power,
+                unit: rnm_3d::LightUnit::Lux,
+                shadows: true,
+            },
+            gltf::khr_lights_punctual::Kind::Point => rnm_3d::Light::Point {
+                position,
+                color,
+                power,
+                unit: rnm_3d::LightUnit::Candela,
+                radius: 0.0,
+                range: light.range(),
+                shadows: true,
+            },
+            gltf::khr_lights_punctual::Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => rnm_3d::Light::Spot {
+                position,
+                direction,
+                color,
+                power,
+                unit: rnm_3d::LightUnit::Candela,
+                radius: 0.0,
+                angle: outer_cone_angle,
+                blend: rnm_3d::Light::cone_to_blend(inner_cone_angle, outer_cone_angle),
+                range: light.range(),
+                shadows: true,
+            },
+        };
+        if let Err(e) = scene.add_light(light) {
+            eprintln!("[ERROR]: {e}!");
+        }
+    }
+    for child in node.children() {
+        load_nodes(child, world, scene);
+    }
+}
+
+fn load_image(image: &gltf::image::Data) -> Option<rnm_3d::Image> {
+    use gltf::image::Format;
+    let floats = || {
+        image
+            .pixels
+            .chunks_exact(4)
+            .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+            .collect::<Vec<_>>()
+    };
+    match image.format {
+        Format::R8G8B8 | Format::R8G8B8A8 => {
+            rnm_3d::Image::from_raw(image.width, image.height, &image.pixels).ok()
+        }
+        // 16 bit channels are little endian, keep the high byte
+        Format::R16G16B16 | Format::R16G16B16A16 => {
+            let pixels = image.pixels.iter().skip(1).step_by(2).copied();
+            rnm_3d::Image::from_raw(image.width, image.height, &pixels.collect::<Vec<_>>()).ok()
+        }
+        Format::R32G32B32FLOAT | Format::R32G32B32A32FLOAT => {
+            rnm_3d::Image::from_hdr(image.width, image.height, &floats()).ok()
+        }
+        _ => None,
+    }
+}
+
+fn layers(
+    material: &gltf::Material,
+    document: &gltf::Document,
+    images: &[Option<rnm_3d::ImageHandle>],
+) -> Vec<rnm_3d::MaterialLayer> {
+    let f32_of = |v: &gltf::json::Value, key: &str, default: f32| {
+        v.get(key)
+            .and_then(|x| x.as_f64())
+            .map_or(default, |x| x as f32)
+    };
+    let texture_of = |v: &gltf::json::Value, key: &str| {
+        let info = v.get(key)?;
+        let texture = document
+            .textures()
+            .nth(info.get("index")?.as_u64()? as usize)?;
+        let tex_coord = info.get("texCoord").and_then(|x| x.as_u64()).unwrap_or(0);
+        let transform = info
+            .get("extensions")
+            .and_then(|x| x.get("KHR_texture_transform"));
+        texture_ref(images, texture, tex_coord as u32, transform)
+    };
+    let mut layers = Vec::new();
+    if let Some(v) = material.extension_value("KHR_materials_clearcoat") {
+        layers.push(rnm_3d::MaterialLayer::Clearcoat {
+            factor: f32_of(v, "clearcoatFactor", 0.0),
+            texture: texture_of(v, "clearcoatTexture"),
+            roughness: f32_of(v, "clearcoatRoughnessFactor", 0.0),
+            roughness_texture: texture_of(v, "clearcoatRoughnessTexture"),
+            normal_texture: texture_of(v, "clearcoatNormalTexture"),
+        });
+    }
+    if let Some(v) = material.extension_value("KHR_materials_sheen") {
+        let color = v
+            .get("sheenColorFactor")
+            .and_then(|x| x.as_array())
+            .and_then(|x| {
+                Some([
+                    x.first()?.as_f64()? as f32,
+                    x.get(1)?.as_f64()? as f32,
+                    x.get(2)?.as_f64()? as f32,
+                ])
+            })
+            .unwrap_or([0.0; 3]);
+        layers.push(rnm_3d::MaterialLayer::Sheen {
+            color,
+            color_texture: texture_of(v, "sheenColorTexture"),
+            roughness: f32_of(v, "sheenRoughnessFactor", 0.0),
+            roughness_texture: texture_of(v, "sheenRoughnessTexture"),
+        });
+    }
+    if let Some(transmission) = material.transmission() {
+        layers.push(rnm_3d::MaterialLayer::Transmission {
+            factor: transmission.transmission_factor(),
+            texture: transmission.transmission_texture().and_then(|t| {
+                texture_ref(
+                    images,
+                    t.texture(),
+                    t.tex_coord(),
+                    t.extension_value("KHR_texture_transform"),
+                )
+            }),
+        });
+    }
+    if let Some(ior) = material.ior() {
+        layers.push(rnm_3d::MaterialLayer::Ior(ior));
+    }
+    if material.unlit() {
+        layers.push(rnm_3d::MaterialLayer::Unlit);
+    }
+    layers
+}
+
+fn texture_ref(
+    images: &[Option<rnm_3d::ImageHandle>],
+    texture: gltf::Texture,
+    tex_coord: u32,
+    transform: Option<&gltf::json::Value>,
+) -> Option<rnm_3d::TextureRef> {
+    let image = images.get(texture.source().index()).copied().flatten()?;
+    let s = texture.sampler();
+    let wrap = |w| match w {
+        gltf::texture::WrappingMode::ClampToEdge => rnm_3d::Wrap::ClampToEdge,
+        gltf::texture::WrappingMode::MirroredRepeat => rnm_3d::Wrap::MirroredRepeat,
+        gltf::texture::WrappingMode::Repeat => rnm_3d::Wrap::Repeat,
+    };
+    let (min_filter, mip_filter) = match s.min_filter() {
+        Some(gltf::texture::MinFilter::Nearest) => (rnm_3d::Filter::Nearest, None),
+        Some(gltf::texture::MinFilter::Linear) => (rnm_3d::Filter::Linear, None),
+        Some(gltf::texture::MinFilter::NearestMipmapNearest) => {
+            (rnm_3d::Filter::Nearest, Some(rnm_3d::Filter::Nearest))
+        }
+        Some(gltf::texture::MinFilter::LinearMipmapNearest) => {
+            (rnm_3d::Filter::Linear, Some(rnm_3d::Filter::Nearest))
+        }
+        Some(gltf::texture::MinFilter::NearestMipmapLinear) => {
+            (rnm_3d::Filter::Nearest, Some(rnm_3d::Filter::Linear))
+        }
+        Some(gltf::texture::MinFilter::LinearMipmapLinear) | None => {
+            (rnm_3d::Filter::Linear, Some(rnm_3d::Filter::Linear))
+        }
+    };
+    let mag_filter = match s.mag_filter() {
+        Some(gltf::texture::MagFilter::Nearest) => rnm_3d::Filter::Nearest,
+        Some(gltf::texture::MagFilter::Linear) | None => rnm_3d::Filter::Linear,
+    };
+    let sampler = rnm_3d::Sampler::new(
+        wrap(s.wrap_s()),
+        wrap(s.wrap_t()),
+        min_filter,
+        mag_filter,
+        mip_filter,
+    );
+    let mut texture = rnm_3d::TextureRef::from(image)
+        .with_sampler(sampler)
+        .with_uv_set(tex_coord);
+    if let Some(transform) = transform {
+        let vec2 = |key: &str, default: [f32; 2]| {
+            transform
+                .get(key)
+                .and_then(|v| v.as_array())
+                .and_then(|v| Some([v.first()?.as_f64()? as f32, v.get(1)?.as_f64()? as f32]))
+                .unwrap_or(default)
+        };
+        let rotation = transform
+            .get("rotation")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0) as f32;
+        texture = texture.with_transform(rnm_3d::TextureTransform::new(
+            vec2("offset", [0.0; 2]),
+            vec2("scale", [1.0; 2]),
+            rotation,
+        ));
+        if let Some(tex_coord) = transform.get("texCoord").and_then(|v| v.as_u64()) {
+            texture = texture.with_uv_set(tex_coord as u32);
+        }
+    }
+    Some(texture)
+}
+
+fn load_mesh(
+    mesh: gltf::Primitive,
+    buffers: &[gltf::buffer::Data],
+    material: Option<rnm_3d::MaterialHandle>,
+) -> Option<rnm_3d::MeshBuilder> {
     let reader = mesh.reader(|buffer| Some(&*buffers[buffer.index()]));
     let indices = if let Some(indices) = reader.read_indices() {
         let indices = indices
@@ -91,19 +448,27 @@ fn load_mesh(mesh: gltf::Primitive, buffers: &[gltf::buffer::Data]) -> Option<rn
         None
     };
     let positions: Vec<[f32; 3]> = reader.read_positions()?.collect();
-    let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
-    let colors: Option<Vec<[f32; 3]>> = reader
-        .read_colors(0)
-        .map(|vec| vec.into_rgb_f32().collect());
-    let uvs: Option<Vec<[f32; 2]>> = reader
-        .read_tex_coords(0)
-        .map(|vec| vec.into_f32().collect());
-    let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|vec| vec.collect());
-    let material = mesh
-        .material()
-        .index()
-        .map(|x| u8::try_from(x).ok())
-        .flatten();
-
-    rnm_3d::Mesh::new(positions, uvs, normals, colors, tangents, indices, material)
+    let mut builder = rnm_3d::MeshBuilder::new(positions);
+    if let Some(normals) = reader.read_normals() {
+        builder = builder.normals(normals.collect());
+    }
+    if let Some(colors) = reader.read_colors(0) {
+        builder = builder.colors(colors.into_rgb_f32().collect());
+    }
+    if let Some(uvs) = reader.read_tex_coords(0) {
+        builder = builder.uvs(uvs.into_f32().collect());
+    }
+    if let Some(uvs) = reader.read_tex_coords(1) {
+        builder = builder.uvs1(uvs.into_f32().collect());
+    }
+    if let Some(tangents) = reader.read_tangents() {
+        builder = builder.tangents(tangents.collect());
+    }
+    if let Some(indices) = indices {
+        builder = builder.indices(indices);
+    }
+    if let Some(material) = material {
+        builder = builder.material(material);
+    }
+    Some(builder)
 }
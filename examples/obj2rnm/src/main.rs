@@ -14,72 +14,90 @@ fn main() -> Result<(), ()> {
     )
     .map_err(|e| eprintln!("[ERROR]: {e:?}"))?;
 
-    let mut textures = HashMap::<String, (u8, rnm_3d::Image)>::new();
-
-    let materials = materials.ok().map(|materials| {
-        materials
-            .into_iter()
-            .map(|material| {
-                let albedo = material
-                    .diffuse_texture
-                    .map(|url| load_texture(url, &mut textures))
-                    .flatten()
-                    .map(rnm_3d::Uniform::Texture)
-                    .unwrap_or(rnm_3d::Uniform::Color(material.diffuse.unwrap_or_default()));
-                let normal_texture = material
-                    .normal_texture
-                    .map(|url| load_texture(url, &mut textures))
-                    .flatten();
-                let roughness = material.shininess.unwrap_or(0.5) / 1000.0;
-                let mre = rnm_3d::Mre::Value {
-                    metallic: 0.0,
-                    roughness,
-                    emission: 0.0,
-                };
-                let specular = material
-                    .specular
-                    .map(|[r, g, b]| r.max(g).max(b))
-                    .unwrap_or(0.5);
-
-                rnm_3d::Material::new(albedo, mre, normal_texture, specular)
-            })
-            .collect()
-    });
-
-    let meshes = meshes
-        .into_iter()
-        .map(|mesh| {
-            let positions = to_vec3(mesh.mesh.positions)
-                .expect("[ERROR]: mesh \"{}\" doesnt have position attribute!");
-            let tex_coords = to_vec2(mesh.mesh.texcoords);
-            let normals = to_vec3(mesh.mesh.normals);
-            let colors = to_vec3(mesh.mesh.vertex_color);
-            let indices = to_vec3u(mesh.mesh.indices);
-            let material = mesh
-                .mesh
-                .material_id
-                .map(|x| u8::try_from(x).ok())
-                .flatten();
-
-            rnm_3d::Mesh::new(
-                positions, tex_coords, normals, colors, None, indices, material,
-            )
+    let mut scene = rnm_3d::SceneBuilder::new();
+    let mut textures = HashMap::<String, (rnm_3d::ImageHandle, bool)>::new();
+
+    let materials = materials
+        .map(|materials| {
+            materials
+                .into_iter()
+                .map(|material| {
+                    let mut builder = rnm_3d::MaterialBuilder::new();
+                    let alpha = material.dissolve.unwrap_or(1.0);
+                    let [r, g, b] = material.diffuse.unwrap_or([1.0; 3]);
+                    builder = builder.albedo_color([r, g, b, alpha]);
+                    let mut has_alpha = false;
+                    if let Some((t, alpha)) = material
+                        .diffuse_texture
+                        .and_then(|url| load_texture(url, &mut textures, &mut scene))
+                    {
+                        builder = builder.albedo_texture(t);
+                        has_alpha = alpha;
+                    }
+                    if alpha < 1.0 {
+                        builder = builder.alpha_mode(rnm_3d::AlphaMode::Blend);
+                    } else if has_alpha || material.dissolve_texture.is_some() {
+                        // cutout textures (foliage, fences) are shown from both sides
+                        builder = builder
+                            .alpha_mode(rnm_3d::AlphaMode::Mask { cutoff: 0.5 })
+                            .double_sided(true);
+                    }
+                    if let Some((t, _)) = material
+                        .normal_texture
+                        .and_then(|url| load_texture(url, &mut textures, &mut scene))
+                    {
+                        builder = builder.normal_texture(t);
+                    }
+                    let roughness = material.shininess.unwrap_or(0.5) / 1000.0;
+                    let specular = material
+                        .specular
+                        .map(|[r, g, b]| r.max(g).max(b))
+                        .unwrap_or(0.5);
+
+                    scene
+                        .add_material(builder.roughness(roughness).specular(specular))
+                        .map_err(|e| eprintln!("[ERROR]: {e}!"))
+                        .ok()
+                })
+                .collect()
         })
-        .collect();
-
-    let images = {
-        if textures.is_empty() {
-            None
-        } else {
-            let mut images = textures.into_values().collect::<Vec<_>>();
-            images.sort_unstable_by_key(|(idx, _)| *idx);
-            Some(images.into_iter().map(|(_, image)| image).collect())
+        .unwrap_or_else(|_| Vec::new());
+
+    for mesh in meshes {
+        let positions = to_vec3(mesh.mesh.positions)
+            .expect("[ERROR]: mesh \"{}\" doesnt have position attribute!");
+        let mut builder = rnm_3d::MeshBuilder::new(positions);
+        if let Some(uvs) = to_vec2(mesh.mesh.texcoords) {
+            builder = builder.uvs(uvs);
+        }
+        if let Some(normals) = to_vec3(mesh.mesh.normals) {
+            builder = builder.normals(normals);
+        }
+        if let Some(colors) = to_vec3(mesh.mesh.vertex_color) {
+            builder = builder.colors(colors);
         }
-    };
+        if let Some(indices) = to_vec3u(mesh.mesh.indices) {
+            builder = builder.indices(indices);
+        }
+        if let Some(material) = mesh
+            .mesh
+            .material_id
+            .and_then(|x| materials.get(x).copied().flatten())
+        {
+            builder = builder.material(material);
+        }
+        scene
+            .add_mesh(builder)
+            .map_err(|e| eprintln!("[ERROR]: mesh \"{}\": {e}!", mesh.name))?;
+    }
 
-    let scene = rnm_3d::Scene::new(meshes, materials, None, images)
-        .expect("[ERROR]: failed to create scene!");
-    scene.save("output.rnm")?;
+    let mut scene = scene.build();
+    scene
+        .generate_mips(rnm_3d::MipFilter::Kaiser)
+        .map_err(|e| eprintln!("[ERROR]: {e}"))?;
+    scene
+        .save("output.rnm")
+        .map_err(|e| eprintln!("[ERROR]: {e}"))?;
     println!("[INFO] scene saved!");
 
     println!("meshes: {}", scene.meshes().map(|v| v.len()).unwrap_or(0));
@@ -151,9 +169,13 @@ fn to_vec3u(vector: Vec<u32>) -> Option<Vec<[u16; 3]>> {
     }
 }
 
-fn load_texture(url: String, textures: &mut HashMap<String, (u8, rnm_3d::Image)>) -> Option<u8> {
-    if let Some((idx, _)) = textures.get(&url) {
-        Some(*idx)
+fn load_texture(
+    url: String,
+    textures: &mut HashMap<String, (rnm_3d::ImageHandle, bool)>,
+    scene: &mut rnm_3d::SceneBuilder,
+) -> Option<(rnm_3d::ImageHandle, bool)> {
+    if let Some(texture) = textures.get(&url) {
+        Some(*texture)
     } else {
         let img = image::load(
             &mut std::io::BufReader::new(std::io::Cursor::new(std::fs::read(&url).ok()?)),
@@ -161,25 +183,27 @@ fn load_texture(url: String, textures: &mut HashMap<String, (u8, rnm_3d::Image)>
         )
         .ok()?;
 
-        let idx = textures.len().try_into().ok()?;
-
-        if img.color().has_alpha() {
-            textures.insert(
-                url,
-                (
-                    idx,
-                    rnm_3d::Image::from_raw(img.width(), img.height(), &img.to_rgba8()).unwrap(),
-                ),
-            );
+        let hdr = matches!(
+            img.color(),
+            image::ColorType::Rgb32F | image::ColorType::Rgba32F
+        );
+        let has_alpha = !hdr && img.color().has_alpha();
+        let handle = if hdr {
+            scene.add_image(
+                rnm_3d::Image::from_hdr(img.width(), img.height(), &img.to_rgb32f()).unwrap(),
+            )
+        } else if has_alpha {
+            scene.add_image(
+                rnm_3d::Image::from_raw(img.width(), img.height(), &img.to_rgba8()).unwrap(),
+            )
         } else {
-            textures.insert(
-                url,
-                (
-                    idx,
-                    rnm_3d::Image::from_raw(img.width(), img.height(), &img.to_rgb8()).unwrap(),
-                ),
-            );
-        };
-        Some(idx)
+            scene.add_image(
+                rnm_3d::Image::from_raw(img.width(), img.height(), &img.to_rgb8()).unwrap(),
+            )
+        }
+        .map_err(|e| eprintln!("[ERROR]: {e}!"))
+        .ok()?;
+        textures.insert(url, (handle, has_alpha));
+        Some((handle, has_alpha))
     }
 }
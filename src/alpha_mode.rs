@@ -0,0 +1,11 @@
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    Mask {
+        cutoff: f32,
+    },
+    Blend,
+}
@@ -0,0 +1,309 @@
+use crate::ImageFormat;
+
+// BC7 4-bit index interpolation weights
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+type Block = [[u8; 4]; 16];
+
+// `rgba` is tightly packed RGBA8, edge pixels are repeated to fill partial blocks
+pub(crate) fn encode(format: ImageFormat, width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(
+        width.div_ceil(4) * height.div_ceil(4) * format.block_size().unwrap_or_default(),
+    );
+    for by in (0..height).step_by(4) {
+        for bx in (0..width).step_by(4) {
+            let mut block = [[0; 4]; 16];
+            for (i, px) in block.iter_mut().enumerate() {
+                let x = (bx + i % 4).min(width - 1);
+                let y = (by + i / 4).min(height - 1);
+                let o = (y * width + x) * 4;
+                px.copy_from_slice(&rgba[o..o + 4]);
+            }
+            match format {
+                ImageFormat::Qoi | ImageFormat::Rgbe | ImageFormat::RgbeLz4 => {}
+                ImageFormat::Bc1 => out.extend(bc1(&block, true)),
+                ImageFormat::Bc3 => {
+                    out.extend(bc4(block.map(|p| p[3])));
+                    out.extend(bc1(&block, false));
+                }
+                ImageFormat::Bc5 => {
+                    out.extend(bc4(block.map(|p| p[0])));
+                    out.extend(bc4(block.map(|p| p[1])));
+                }
+                ImageFormat::Bc7 => out.extend(bc7(&block)),
+            }
+        }
+    }
+    out
+}
+
+fn dist(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+fn nearest(p: &[f32; 4], palette: &[[f32; 4]]) -> usize {
+    (0..palette.len())
+        .min_by(|a, b| dist(p, &palette[*a]).total_cmp(&dist(p, &palette[*b])))
+        .unwrap_or_default()
+}
+
+// extremes of the block along its principal axis, over the first `dims` channels
+fn endpoints(px: &[[f32; 4]; 16], dims: usize) -> ([f32; 4], [f32; 4]) {
+    let mut mean = [0.0; 4];
+    for p in px {
+        for c in 0..dims {
+            mean[c] += p[c] / 16.0;
+        }
+    }
+    let mut cov = [[0.0; 4]; 4];
+    for p in px {
+        for i in 0..dims {
+            for j in 0..dims {
+                cov[i][j] += (p[i] - mean[i]) * (p[j] - mean[j]);
+            }
+        }
+    }
+    let mut axis = [1.0; 4];
+    for _ in 0..8 {
+        let mut next = [0.0; 4];
+        for i in 0..dims {
+            for j in 0..dims {
+                next[i] += cov[i][j] * axis[j];
+            }
+        }
+        let len = next.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if len < 1e-6 {
+            break;
+        }
+        axis = next.map(|x| x / len);
+    }
+    let t = px.map(|p| (0..dims).map(|c| (p[c] - mean[c]) * axis[c]).sum::<f32>());
+    let (lo, hi) = t
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), t| (lo.min(*t), hi.max(*t)));
+    let point = |t: f32| {
+        let mut p = [0.0; 4];
+        for c in 0..dims {
+            p[c] = (mean[c] + axis[c] * t).clamp(0.0, 255.0);
+        }
+        p
+    };
+    (point(lo), point(hi))
+}
+
+fn to565(c: &[f32; 4]) -> u16 {
+    let r = (c[0] * 31.0 / 255.0).round() as u16;
+    let g = (c[1] * 63.0 / 255.0).round() as u16;
+    let b = (c[2] * 31.0 / 255.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn from565(c: u16) -> [f32; 4] {
+    let r = (c >> 11) & 31;
+    let g = (c >> 5) & 63;
+    let b = c & 31;
+    [
+        ((r << 3) | (r >> 2)) as f32,
+        ((g << 2) | (g >> 4)) as f32,
+        ((b << 3) | (b >> 2)) as f32,
+        0.0,
+    ]
+}
+
+fn lerp(a: &[f32; 4], b: &[f32; 4], t: f32) -> [f32; 4] {
+    [0, 1, 2, 3].map(|c| a[c] + (b[c] - a[c]) * t)
+}
+
+// `alpha` enables the 3 color + transparent mode for pixels with alpha < 128
+fn bc1(block: &Block, alpha: bool) -> [u8; 8] {
+    let transparent = alpha && block.iter().any(|p| p[3] < 128);
+    let px = block.map(|p| [p[0] as f32, p[1] as f32, p[2] as f32, 0.0]);
+    let (lo, hi) = endpoints(&px, 3);
+    let (mut c0, mut c1) = (to565(&hi), to565(&lo));
+    if transparent == (c0 > c1) {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+    let (e0, e1) = (from565(c0), from565(c1));
+    let palette = if c0 > c1 {
+        [e0, e1, lerp(&e0, &e1, 1.0 / 3.0), lerp(&e0, &e1, 2.0 / 3.0)]
+    } else {
+        [e0, e1, lerp(&e0, &e1, 0.5), [0.0; 4]]
+    };
+    let colors = if c0 > c1 { 4 } else { 3 };
+    let mut indices = 0u32;
+    for (i, p) in px.iter().enumerate() {
+        let index = if transparent && block[i][3] < 128 {
+            3
+        } else {
+            nearest(p, &palette[..colors])
+        };
+        indices |= (index as u32) << (i * 2);
+    }
+    let mut out = [0; 8];
+    out[..2].copy_from_slice(&c0.to_le_bytes());
+    out[2..4].copy_from_slice(&c1.to_le_bytes());
+    out[4..].copy_from_slice(&indices.to_le_bytes());
+    out
+}
+
+// single channel block, also used for BC3 alpha and the two BC5 channels
+fn bc4(values: [u8; 16]) -> [u8; 8] {
+    let (a0, a1) = (
+        values.iter().copied().max().unwrap_or_default(),
+        values.iter().copied().min().unwrap_or_default(),
+    );
+    let mut out = [a0, a1, 0, 0, 0, 0, 0, 0];
+    if a0 == a1 {
+        return out;
+    }
+    let mut palette = [a0 as f32, a1 as f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+    for (i, p) in palette.iter_mut().enumerate().skip(2) {
+        *p = ((8 - i) as f32 * a0 as f32 + (i - 1) as f32 * a1 as f32) / 7.0;
+    }
+    let mut indices = 0u64;
+    for (i, v) in values.iter().enumerate() {
+        let index = (0..8)
+            .min_by(|a, b| {
+                (palette[*a] - *v as f32)
+                    .abs()
+                    .total_cmp(&(palette[*b] - *v as f32).abs())
+            })
+            .unwrap_or_default();
+        indices |= (index as u64) << (i * 3);
+    }
+    out[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    out
+}
+
+// 7 bit endpoint sharing a p-bit across channels, picks the p-bit with less error
+fn bc7_quantize(e: &[f32; 4]) -> ([u8; 4], u8) {
+    (0..2u8)
+        .map(|p| {
+            let q = e.map(|c| ((c - p as f32) / 2.0).round().clamp(0.0, 127.0) as u8);
+            let err = (0..4)
+                .map(|c| (((q[c] << 1) | p) as f32 - e[c]).powi(2))
+                .sum::<f32>();
+            (q, p, err)
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(q, p, _)| (q, p))
+        .unwrap_or_default()
+}
+
+// mode 6 only: one subset, RGBA 7.7.7.7 endpoints + p-bits, 4 bit indices
+fn bc7(block: &Block) -> [u8; 16] {
+    let px = block.map(|p| p.map(f32::from));
+    let (lo, hi) = endpoints(&px, 4);
+    let mut ends = [bc7_quantize(&lo), bc7_quantize(&hi)];
+    let expand = |(q, p): ([u8; 4], u8)| q.map(|c| ((c << 1) | p) as u32);
+    let palette = |ends: &[([u8; 4], u8); 2]| {
+        let (e0, e1) = (expand(ends[0]), expand(ends[1]));
+        BC7_WEIGHTS.map(|w| [0, 1, 2, 3].map(|c| (((64 - w) * e0[c] + w * e1[c] + 32) >> 6) as f32))
+    };
+    let mut indices = px.map(|p| nearest(&p, &palette(&ends)));
+    // the anchor index has an implicit 0 msb
+    if indices[0] >= 8 {
+        ends.swap(0, 1);
+        indices = indices.map(|i| 15 - i);
+    }
+    let mut bits = 1u128 << 6;
+    let mut pos = 7;
+    let mut push = |value: u128, len: usize| {
+        bits |= value << pos;
+        pos += len;
+    };
+    for c in 0..4 {
+        push(ends[0].0[c] as u128, 7);
+        push(ends[1].0[c] as u128, 7);
+    }
+    push(ends[0].1 as u128, 1);
+    push(ends[1].1 as u128, 1);
+    for (i, index) in indices.iter().enumerate() {
+        push(*index as u128, if i == 0 { 3 } else { 4 });
+    }
+    bits.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, BC7_WEIGHTS};
+    use crate::ImageFormat;
+
+    fn gradient(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|i| [(i * 16) as u8, (i * 8) as u8, 255 - (i * 4) as u8, 255])
+            .collect()
+    }
+
+    #[test]
+    fn block_sizes() {
+        for (format, size) in [
+            (ImageFormat::Bc1, 8),
+            (ImageFormat::Bc3, 16),
+            (ImageFormat::Bc5, 16),
+            (ImageFormat::Bc7, 16),
+        ] {
+            assert_eq!(format.block_size(), Some(size));
+            // partial blocks still take a whole block
+            for (width, height, blocks) in [(1, 1, 1), (4, 4, 1), (5, 3, 2), (8, 9, 6)] {
+                let out = encode(format, width, height, &gradient(width, height));
+                assert_eq!(out.len(), blocks * size, "{format:?} {width}x{height}");
+            }
+        }
+    }
+
+    #[test]
+    fn bc1_modes() {
+        let block = |alpha: u8| {
+            let mut rgba = gradient(4, 4);
+            rgba[3] = alpha;
+            encode(ImageFormat::Bc1, 4, 4, &rgba)
+        };
+        let endpoints = |b: &[u8]| {
+            (
+                u16::from_le_bytes([b[0], b[1]]),
+                u16::from_le_bytes([b[2], b[3]]),
+            )
+        };
+        // opaque blocks use the 4 color mode, c0 > c1
+        let (c0, c1) = endpoints(&block(255));
+        assert!(c0 > c1);
+        // a transparent pixel needs the 3 color mode and index 3
+        let out = block(0);
+        let (c0, c1) = endpoints(&out);
+        assert!(c0 <= c1);
+        assert_eq!(out[4] & 3, 3);
+    }
+
+    // decodes a mode 6 block back to RGBA
+    fn bc7_decode(block: &[u8]) -> [[u8; 4]; 16] {
+        let bits = u128::from_le_bytes(block.try_into().unwrap());
+        let mut pos = 7;
+        let mut take = |len: usize| {
+            let value = (bits >> pos) & ((1 << len) - 1);
+            pos += len;
+            value as u32
+        };
+        // stored channel by channel, both endpoints per channel
+        let channels: [[u32; 2]; 4] = std::array::from_fn(|_| [take(7), take(7)]);
+        let p = [take(1), take(1)];
+        let ends = [0, 1].map(|e| channels.map(|c| (c[e] << 1) | p[e]));
+        std::array::from_fn(|i| {
+            let w = BC7_WEIGHTS[take(if i == 0 { 3 } else { 4 }) as usize];
+            [0, 1, 2, 3].map(|c| (((64 - w) * ends[0][c] + w * ends[1][c] + 32) >> 6) as u8)
+        })
+    }
+
+    #[test]
+    fn bc7_mode_6() {
+        let rgba = gradient(4, 4);
+        let out = encode(ImageFormat::Bc7, 4, 4, &rgba);
+        // mode 6 is a single 1 bit after six 0 bits
+        assert_eq!(out[0] & 0x7f, 0x40);
+        for (decoded, source) in bc7_decode(&out).iter().zip(rgba.chunks_exact(4)) {
+            for (d, s) in decoded.iter().zip(source) {
+                assert!(d.abs_diff(*s) <= 8, "{decoded:?} {source:?}");
+            }
+        }
+    }
+}
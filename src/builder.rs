@@ -0,0 +1,396 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{
+    AlphaMode, Camera, Environment, Image, ImageHandle, Light, Lightmap, Material, MaterialHandle,
+    MaterialLayer, Mesh, Mre, ProbeVolume, Scene, Section, TextureRef, Uniform, ValidationError,
+};
+
+// handles can only be created by a `SceneBuilder` and carry its id, so every
+// reference a builder accepts points inside the scene being built. raw
+// indices and handles from another builder are rejected.
+
+#[derive(Debug)]
+pub struct SceneBuilder {
+    id: u32,
+    meshes: Vec<Mesh>,
+    materials: Vec<Material>,
+    lights: Vec<Light>,
+    images: Vec<Image>,
+    cameras: Vec<Camera>,
+    environment: Option<Environment>,
+    probe_volumes: Vec<ProbeVolume>,
+}
+
+impl Default for SceneBuilder {
+    fn default() -> Self {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            lights: Vec::new(),
+            images: Vec::new(),
+            cameras: Vec::new(),
+            environment: None,
+            probe_volumes: Vec::new(),
+        }
+    }
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn owns(&self, builder: Option<u32>) -> bool {
+        builder == Some(self.id)
+    }
+    pub fn add_image(&mut self, image: Image) -> Result<ImageHandle, ValidationError> {
+        let mut errors = Vec::new();
+        image.validate(self.images.len(), &mut errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        let Ok(idx) = u32::try_from(self.images.len()) else {
+            return Err(ValidationError::TooManyItems {
+                section: Section::Images,
+                len: self.images.len() + 1,
+            });
+        };
+        self.images.push(image);
+        Ok(ImageHandle::new(self.id, idx))
+    }
+    pub fn add_material(
+        &mut self,
+        material: MaterialBuilder,
+    ) -> Result<MaterialHandle, ValidationError> {
+        let material = material.build();
+        if let Some(texture) = material.textures().find(|t| !self.owns(t.builder())) {
+            return Err(ValidationError::ForeignImage {
+                image: texture.image(),
+            });
+        }
+        let mut errors = Vec::new();
+        material.validate(self.materials.len(), self.images.len(), &mut errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        let Ok(idx) = u32::try_from(self.materials.len()) else {
+            return Err(ValidationError::TooManyItems {
+                section: Section::Materials,
+                len: self.materials.len() + 1,
+            });
+        };
+        self.materials.push(material);
+        Ok(MaterialHandle::new(self.id, idx))
+    }
+    pub fn add_mesh(&mut self, mesh: MeshBuilder) -> Result<(), ValidationError> {
+        if let Some(material) = mesh.material.filter(|m| m.builder() != self.id) {
+            return Err(ValidationError::ForeignMaterial {
+                material: material.index(),
+            });
+        }
+        if let Some(lightmap) = mesh.lightmap.filter(|l| !self.owns(l.builder())) {
+            return Err(ValidationError::ForeignImage {
+                image: lightmap.image(),
+            });
+        }
+        let mesh = mesh.build();
+        let mut errors = Vec::new();
+        mesh.validate(
+            self.meshes.len(),
+            self.materials.len(),
+            self.images.len(),
+            &mut errors,
+        );
+        mesh.validate_uv_sets(self.meshes.len(), &self.materials, &mut errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        self.meshes.push(mesh);
+        Ok(())
+    }
+    pub fn add_light(&mut self, light: Light) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        light.validate(self.lights.len(), &mut errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        self.lights.push(light);
+        Ok(())
+    }
+    pub fn add_camera(&mut self, camera: Camera) {
+        self.cameras.push(camera);
+    }
+    pub fn add_probe_volume(&mut self, volume: ProbeVolume) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        volume.validate(self.probe_volumes.len(), &mut errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        self.probe_volumes.push(volume);
+        Ok(())
+    }
+    pub fn set_environment(
+        &mut self,
+        environment: impl Into<Environment>,
+    ) -> Result<(), ValidationError> {
+        let environment = environment.into();
+        if !self.owns(environment.builder()) {
+            return Err(ValidationError::ForeignImage {
+                image: environment.image(),
+            });
+        }
+        let mut errors = Vec::new();
+        environment.validate(self.images.len(), &mut errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        self.environment = Some(environment);
+        Ok(())
+    }
+    pub fn build(self) -> Scene {
+        fn some<T>(v: Vec<T>) -> Option<Vec<T>> {
+            if v.is_empty() {
+                None
+            } else {
+                Some(v)
+            }
+        }
+        let mut scene = Scene::from_parts(
+            some(self.meshes),
+            some(self.materials),
+            some(self.lights),
+            some(self.images),
+            some(self.cameras),
+            self.environment,
+            some(self.probe_volumes),
+        );
+        scene.tag_color_spaces();
+        scene
+    }
+}
+
+#[derive(Debug)]
+pub struct MaterialBuilder {
+    albedo: [f32; 4],
+    albedo_texture: Option<TextureRef>,
+    metallic: f32,
+    roughness: f32,
+    metallic_roughness_texture: Option<TextureRef>,
+    normal_texture: Option<TextureRef>,
+    normal_scale: f32,
+    occlusion_texture: Option<TextureRef>,
+    occlusion_strength: f32,
+    emissive: [f32; 3],
+    emissive_texture: Option<TextureRef>,
+    emissive_strength: f32,
+    alpha_mode: AlphaMode,
+    double_sided: bool,
+    specular: f32,
+    layers: Vec<MaterialLayer>,
+}
+
+impl Default for MaterialBuilder {
+    fn default() -> Self {
+        Self {
+            albedo: [1.0; 4],
+            albedo_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: [0.0; 3],
+            emissive_texture: None,
+            emissive_strength: 1.0,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            specular: 0.5,
+            layers: Vec::new(),
+        }
+    }
+}
+
+impl MaterialBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub const fn albedo_color(mut self, color: [f32; 4]) -> Self {
+        self.albedo = color;
+        self
+    }
+    pub fn albedo_texture(mut self, texture: impl Into<TextureRef>) -> Self {
+        self.albedo_texture = Some(texture.into());
+        self
+    }
+    pub const fn metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic;
+        self
+    }
+    pub const fn roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+    pub fn metallic_roughness_texture(mut self, texture: impl Into<TextureRef>) -> Self {
+        self.metallic_roughness_texture = Some(texture.into());
+        self
+    }
+    pub fn normal_texture(mut self, texture: impl Into<TextureRef>) -> Self {
+        self.normal_texture = Some(texture.into());
+        self
+    }
+    pub const fn normal_scale(mut self, scale: f32) -> Self {
+        self.normal_scale = scale;
+        self
+    }
+    pub fn occlusion_texture(mut self, texture: impl Into<TextureRef>) -> Self {
+        self.occlusion_texture = Some(texture.into());
+        self
+    }
+    pub const fn occlusion_strength(mut self, strength: f32) -> Self {
+        self.occlusion_strength = strength;
+        self
+    }
+    pub const fn emissive(mut self, color: [f32; 3]) -> Self {
+        self.emissive = color;
+        self
+    }
+    pub fn emissive_texture(mut self, texture: impl Into<TextureRef>) -> Self {
+        self.emissive_texture = Some(texture.into());
+        self
+    }
+    pub const fn emissive_strength(mut self, strength: f32) -> Self {
+        self.emissive_strength = strength;
+        self
+    }
+    pub const fn alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+    pub const fn double_sided(mut self, double_sided: bool) -> Self {
+        self.double_sided = double_sided;
+        self
+    }
+    pub const fn specular(mut self, specular: f32) -> Self {
+        self.specular = specular;
+        self
+    }
+    pub fn layer(mut self, layer: MaterialLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+    fn build(self) -> Material {
+        let albedo = match self.albedo_texture {
+            Some(texture) => Uniform::Texture {
+                texture,
+                factor: self.albedo,
+            },
+            None => Uniform::Color(self.albedo),
+        };
+        let metallic_roughness = match self.metallic_roughness_texture {
+            Some(texture) => Mre::Texture {
+                texture,
+                metallic: self.metallic,
+                roughness: self.roughness,
+            },
+            None => Mre::Value {
+                metallic: self.metallic,
+                roughness: self.roughness,
+            },
+        };
+        let material = Material::new(
+            albedo,
+            metallic_roughness,
+            self.normal_texture,
+            self.specular,
+        )
+        .with_normal_scale(self.normal_scale)
+        .with_occlusion(self.occlusion_texture, self.occlusion_strength)
+        .with_emissive(self.emissive, self.emissive_texture, self.emissive_strength)
+        .with_alpha_mode(self.alpha_mode)
+        .with_double_sided(self.double_sided);
+        self.layers.into_iter().fold(material, Material::with_layer)
+    }
+}
+
+#[derive(Debug)]
+pub struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    uvs: Option<Vec<[f32; 2]>>,
+    normals: Option<Vec<[f32; 3]>>,
+    colors: Option<Vec<[f32; 3]>>,
+    tangents: Option<Vec<[f32; 4]>>,
+    indices: Option<Vec<[u16; 3]>>,
+    material: Option<MaterialHandle>,
+    lightmap: Option<Lightmap>,
+    uvs1: Option<Vec<[f32; 2]>>,
+}
+
+impl MeshBuilder {
+    pub const fn new(positions: Vec<[f32; 3]>) -> Self {
+        Self {
+            positions,
+            uvs: None,
+            normals: None,
+            colors: None,
+            tangents: None,
+            indices: None,
+            material: None,
+            lightmap: None,
+            uvs1: None,
+        }
+    }
+    pub fn uvs(mut self, uvs: Vec<[f32; 2]>) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+    pub fn uvs1(mut self, uvs1: Vec<[f32; 2]>) -> Self {
+        self.uvs1 = Some(uvs1);
+        self
+    }
+    pub fn normals(mut self, normals: Vec<[f32; 3]>) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+    pub fn colors(mut self, colors: Vec<[f32; 3]>) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+    pub fn tangents(mut self, tangents: Vec<[f32; 4]>) -> Self {
+        self.tangents = Some(tangents);
+        self
+    }
+    pub fn indices(mut self, indices: Vec<[u16; 3]>) -> Self {
+        self.indices = Some(indices);
+        self
+    }
+    pub fn material(mut self, material: MaterialHandle) -> Self {
+        self.material = Some(material);
+        self
+    }
+    pub fn lightmap(mut self, lightmap: impl Into<Lightmap>) -> Self {
+        self.lightmap = Some(lightmap.into());
+        self
+    }
+    fn build(self) -> Mesh {
+        let mesh = Mesh::from_parts(
+            self.positions,
+            self.uvs,
+            self.normals,
+            self.colors,
+            self.tangents,
+            self.indices,
+            self.material.map(MaterialHandle::index),
+        );
+        let mesh = match self.uvs1 {
+            Some(uvs1) => mesh.with_uvs1(uvs1),
+            None => mesh,
+        };
+        match self.lightmap {
+            Some(lightmap) => mesh.with_lightmap(lightmap),
+            None => mesh,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+#[derive(Debug, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub enum Projection {
+    // no aspect means the viewport one, no zfar means an infinite projection
+    Perspective {
+        yfov: f32,
+        aspect: Option<f32>,
+        znear: f32,
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct Camera {
+    position: [f32; 3],
+    // quaternion (x, y, z, w), the camera looks down -Z with +Y up
+    rotation: [f32; 4],
+    projection: Projection,
+}
+
+impl Camera {
+    pub const fn new(projection: Projection) -> Self {
+        Self {
+            position: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            projection,
+        }
+    }
+    pub const fn with_position(mut self, position: [f32; 3]) -> Self {
+        self.position = position;
+        self
+    }
+    pub const fn with_rotation(mut self, rotation: [f32; 4]) -> Self {
+        self.rotation = rotation;
+        self
+    }
+    pub const fn position(&self) -> &[f32; 3] {
+        &self.position
+    }
+    pub const fn rotation(&self) -> &[f32; 4] {
+        &self.rotation
+    }
+    pub const fn projection(&self) -> &Projection {
+        &self.projection
+    }
+    // row-major, OpenGL clip space (z in -1..1) like the glTF spec,
+    // `aspect` (width / height) is only used when the camera has none
+    pub fn projection_matrix(&self, aspect: f32) -> [[f32; 4]; 4] {
+        match self.projection {
+            Projection::Perspective {
+                yfov,
+                aspect: a,
+                znear: n,
+                zfar,
+            } => {
+                let t = (yfov * 0.5).tan();
+                let a = a.unwrap_or(aspect);
+                let (z, w) = match zfar {
+                    Some(f) => ((f + n) / (n - f), 2.0 * f * n / (n - f)),
+                    None => (-1.0, -2.0 * n),
+                };
+                [
+                    [1.0 / (a * t), 0.0, 0.0, 0.0],
+                    [0.0, 1.0 / t, 0.0, 0.0],
+                    [0.0, 0.0, z, w],
+                    [0.0, 0.0, -1.0, 0.0],
+                ]
+            }
+            Projection::Orthographic {
+                xmag,
+                ymag,
+                znear: n,
+                zfar: f,
+            } => [
+                [1.0 / xmag, 0.0, 0.0, 0.0],
+                [0.0, 1.0 / ymag, 0.0, 0.0],
+                [0.0, 0.0, 2.0 / (n - f), (f + n) / (n - f)],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+}
@@ -0,0 +1,35 @@
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+impl From<ColorSpace> for qoi::ColorSpace {
+    fn from(c: ColorSpace) -> Self {
+        match c {
+            ColorSpace::Srgb => Self::Srgb,
+            ColorSpace::Linear => Self::Linear,
+        }
+    }
+}
+
+impl From<&ArchivedColorSpace> for ColorSpace {
+    fn from(c: &ArchivedColorSpace) -> Self {
+        match c {
+            ArchivedColorSpace::Srgb => Self::Srgb,
+            ArchivedColorSpace::Linear => Self::Linear,
+        }
+    }
+}
+
+impl From<qoi::ColorSpace> for ColorSpace {
+    fn from(c: qoi::ColorSpace) -> Self {
+        match c {
+            qoi::ColorSpace::Srgb => Self::Srgb,
+            qoi::ColorSpace::Linear => Self::Linear,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// how a section is stored, kept in the table of contents with its level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Lz4,
+    // same lz4 block format, searches harder for matches, level 1..=12 (clamped)
+    Lz4Hc(u8),
+    // `zstd` feature, level 1..=22 (clamped)
+    Zstd(i32),
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        if cfg!(feature = "compression") {
+            Self::Lz4
+        } else {
+            Self::None
+        }
+    }
+}
+
+impl Compression {
+    pub(crate) const fn id(self) -> u16 {
+        match self {
+            Self::None => 0,
+            Self::Lz4 => 1,
+            Self::Lz4Hc(_) => 2,
+            Self::Zstd(_) => 3,
+        }
+    }
+    // out of range levels are clamped before compressing, so the table of
+    // contents always records the level that was used
+    pub(crate) fn clamped(self) -> Self {
+        match self {
+            Self::Lz4Hc(level) => Self::Lz4Hc(level.clamp(1, 12)),
+            Self::Zstd(level) => Self::Zstd(level.clamp(1, 22)),
+            other => other,
+        }
+    }
+    pub(crate) fn level(self) -> i16 {
+        match self.clamped() {
+            Self::None | Self::Lz4 => 0,
+            Self::Lz4Hc(level) => level.into(),
+            Self::Zstd(level) => level as i16,
+        }
+    }
+    pub(crate) fn from_id(id: u16, level: i16) -> Option<Self> {
+        let compression = match id {
+            0 => Self::None,
+            1 => Self::Lz4,
+            2 => Self::Lz4Hc(level.clamp(1, 12) as u8),
+            3 => Self::Zstd(level.into()),
+            _ => return None,
+        };
+        Some(compression.clamped())
+    }
+}
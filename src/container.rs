@@ -0,0 +1,244 @@
+use std::borrow::Cow;
+
+use rkyv::util::AlignedVec;
+
+use crate::{Compression, Error, SaveOptions, Section};
+
+// file layout, everything little endian:
+//   header  magic "RNM3", version u32, section count u32, crc32 u32 of the
+//           header (without it) and toc, so offsets are trusted before use
+//   toc     one `ENTRY` byte record per section: section u32, codec u16,
+//           level i16, offset u64, size u64, raw size u64, crc32 u32, count u32
+//   data    each section starts on an `ALIGN` boundary so it can be read in place
+pub(crate) const MAGIC: &[u8; 4] = b"RNM3";
+const VERSION: u32 = 3;
+const HEADER: usize = 16;
+const ENTRY: usize = 40;
+pub(crate) const ALIGN: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Entry {
+    pub(crate) section: Section,
+    codec: u16,
+    // `None` for codecs from newer versions
+    pub(crate) compression: Option<Compression>,
+    offset: usize,
+    size: usize,
+    raw_size: usize,
+    // crc32 of the stored (compressed) bytes
+    checksum: u32,
+    // number of items, lets a section be checked against ones not loaded
+    pub(crate) count: u32,
+}
+
+pub(crate) struct Container<'a> {
+    data: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32, Error> {
+    let bytes = data.get(at..at + 4).ok_or(Error::Truncated)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap_or_default()))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Result<usize, Error> {
+    let bytes = data.get(at..at + 8).ok_or(Error::Truncated)?;
+    usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+        .map_err(|_| Error::Truncated)
+}
+
+fn header_checksum(header: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header[..12]);
+    hasher.update(&header[HEADER..]);
+    hasher.finalize()
+}
+
+pub(crate) fn is_container(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+impl<'a> Container<'a> {
+    pub(crate) fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        if !is_container(data) {
+            return Err(Error::BadMagic);
+        }
+        let version = read_u32(data, 4)?;
+        if version != VERSION {
+            return Err(Error::Version(version));
+        }
+        let count = read_u32(data, 8)? as usize;
+        let toc = count
+            .checked_mul(ENTRY)
+            .and_then(|toc| toc.checked_add(HEADER))
+            .and_then(|end| data.get(..end))
+            .ok_or(Error::Truncated)?;
+        if header_checksum(toc) != read_u32(data, 12)? {
+            return Err(Error::HeaderChecksum);
+        }
+        let mut entries = Vec::with_capacity(count.min(Section::ALL.len()));
+        for i in 0..count {
+            let at = HEADER + i * ENTRY;
+            let offset = read_u64(data, at + 8)?;
+            let size = read_u64(data, at + 16)?;
+            if offset.checked_add(size).is_none_or(|end| end > data.len()) {
+                return Err(Error::Truncated);
+            }
+            // sections written by newer versions are skipped
+            let Some(section) = Section::from_id(read_u32(data, at)?) else {
+                continue;
+            };
+            let codec = read_u32(data, at + 4)?;
+            let (id, level) = (codec as u16, (codec >> 16) as i16);
+            entries.push(Entry {
+                section,
+                codec: id,
+                compression: Compression::from_id(id, level),
+                offset,
+                size,
+                raw_size: read_u64(data, at + 24)?,
+                checksum: read_u32(data, at + 32)?,
+                count: read_u32(data, at + 36)?,
+            });
+        }
+        Ok(Self { data, entries })
+    }
+    pub(crate) fn entry(&self, section: Section) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.section == section)
+    }
+    pub(crate) fn count(&self, section: Section) -> usize {
+        self.entry(section).map_or(0, |e| e.count as usize)
+    }
+    fn stored(&self, entry: &Entry) -> Result<&'a [u8], Error> {
+        let stored = &self.data[entry.offset..entry.offset + entry.size];
+        if crc32fast::hash(stored) != entry.checksum {
+            return Err(Error::Checksum {
+                section: entry.section,
+            });
+        }
+        Ok(stored)
+    }
+    // the range of a section that can be read where it is: uncompressed and
+    // aligned (a mapped file starts on a page, a slice may start anywhere)
+    #[cfg(feature = "mmap")]
+    pub(crate) fn in_place(&self, entry: &Entry) -> Result<Option<std::ops::Range<usize>>, Error> {
+        if entry.compression != Some(Compression::None) || entry.size != entry.raw_size {
+            return Ok(None);
+        }
+        let stored = self.stored(entry)?;
+        Ok((stored.as_ptr() as usize)
+            .is_multiple_of(ALIGN)
+            .then(|| entry.offset..entry.offset + entry.size))
+    }
+    // checks and decompresses one section into an aligned buffer
+    pub(crate) fn read(&self, entry: &Entry) -> Result<AlignedVec<ALIGN>, Error> {
+        let section = entry.section;
+        let stored = self.stored(entry)?;
+        let mut out = AlignedVec::<ALIGN>::with_capacity(entry.raw_size);
+        match entry.compression {
+            Some(Compression::None) => out.extend_from_slice(stored),
+            #[cfg(feature = "compression")]
+            Some(Compression::Lz4 | Compression::Lz4Hc(_)) => {
+                out.resize(entry.raw_size, 0);
+                let len = lz4_flex::block::decompress_into(stored, &mut out)
+                    .map_err(|_| Error::Decompress { section })?;
+                if len != entry.raw_size {
+                    return Err(Error::Decompress { section });
+                }
+            }
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd(_)) => {
+                out.resize(entry.raw_size, 0);
+                let len = zstd::bulk::decompress_to_buffer(stored, out.as_mut_slice())
+                    .map_err(|_| Error::Decompress { section })?;
+                if len != entry.raw_size {
+                    return Err(Error::Decompress { section });
+                }
+            }
+            #[cfg(not(feature = "compression"))]
+            Some(Compression::Lz4 | Compression::Lz4Hc(_)) => {
+                return Err(Error::CompressionDisabled)
+            }
+            #[cfg(not(feature = "zstd"))]
+            Some(Compression::Zstd(_)) => return Err(Error::CompressionDisabled),
+            None => {
+                return Err(Error::Codec {
+                    section,
+                    codec: u32::from(entry.codec),
+                })
+            }
+        }
+        if out.len() != entry.raw_size {
+            return Err(Error::Decompress { section });
+        }
+        Ok(out)
+    }
+}
+
+// falls back to storing the section as is when it doesn't shrink
+fn compress(raw: &[u8], compression: Compression) -> Result<(Compression, Cow<'_, [u8]>), Error> {
+    let compression = compression.clamped();
+    let packed: Option<Vec<u8>> = match compression {
+        Compression::None => None,
+        #[cfg(feature = "compression")]
+        Compression::Lz4 => Some(lz4_flex::block::compress(raw)),
+        #[cfg(feature = "compression")]
+        Compression::Lz4Hc(level) => Some(crate::lz4hc::compress(raw, level)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(level) => Some(zstd::bulk::compress(raw, level)?),
+        #[cfg(not(feature = "compression"))]
+        Compression::Lz4 | Compression::Lz4Hc(_) => return Err(Error::CompressionDisabled),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd(_) => return Err(Error::CompressionDisabled),
+    };
+    Ok(match packed {
+        Some(packed) if packed.len() < raw.len() => (compression, packed.into()),
+        _ => (Compression::None, raw.into()),
+    })
+}
+
+// `chunks` are (section, item count, serialized section), every offset is
+// known up front so sections are written as they are, without building the
+// whole file in memory
+pub(crate) fn write(
+    chunks: &[(Section, u32, AlignedVec<ALIGN>)],
+    options: &SaveOptions,
+    mut writer: impl std::io::Write,
+) -> Result<(), Error> {
+    let stored = chunks
+        .iter()
+        .map(|(section, _, raw)| compress(raw, options.compression(*section)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut header = Vec::with_capacity(HEADER + chunks.len() * ENTRY);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+    // checksum, filled in once the toc is written
+    header.extend_from_slice(&0u32.to_le_bytes());
+    let mut offsets = Vec::with_capacity(chunks.len());
+    let mut end = HEADER + chunks.len() * ENTRY;
+    for ((section, count, raw), (compression, data)) in chunks.iter().zip(&stored) {
+        let offset = end.next_multiple_of(ALIGN);
+        offsets.push(offset);
+        end = offset + data.len();
+        header.extend_from_slice(&section.id().to_le_bytes());
+        header.extend_from_slice(&compression.id().to_le_bytes());
+        header.extend_from_slice(&compression.level().to_le_bytes());
+        header.extend_from_slice(&(offset as u64).to_le_bytes());
+        header.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(raw.len() as u64).to_le_bytes());
+        header.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+        header.extend_from_slice(&count.to_le_bytes());
+    }
+    let checksum = header_checksum(&header);
+    header[12..HEADER].copy_from_slice(&checksum.to_le_bytes());
+    writer.write_all(&header)?;
+    let mut at = header.len();
+    for ((_, data), offset) in stored.iter().zip(offsets) {
+        writer.write_all(&[0; ALIGN][..offset - at])?;
+        writer.write_all(data)?;
+        at = offset + data.len();
+    }
+    writer.flush()?;
+    Ok(())
+}
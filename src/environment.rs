@@ -0,0 +1,123 @@
+use crate::{ibl, ImageHandle, ValidationError};
+
+// image based lighting from an equirectangular image, u = 0.5 looks down -Z
+// and v = 0 is +Y. `rotation` turns the sky around +Y (radians) and applies
+// to the image, `irradiance` and `specular` alike.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct Environment {
+    image: u32,
+    intensity: f32,
+    rotation: f32,
+    // L2 spherical harmonics (RGB) already convolved with the cosine lobe,
+    // evaluating them at a normal gives irradiance, divide by π for radiance
+    irradiance: Option<[[f32; 3]; 9]>,
+    // equirectangular image, mip i is prefiltered for roughness i / (levels - 1)
+    specular: Option<u32>,
+    // set when made from an `ImageHandle`, never stored
+    #[rkyv(with = rkyv::with::Skip)]
+    builder: Option<u32>,
+}
+
+impl ArchivedEnvironment {
+    pub fn image(&self) -> u32 {
+        self.image.to_native()
+    }
+    pub fn intensity(&self) -> f32 {
+        self.intensity.to_native()
+    }
+    pub fn rotation(&self) -> f32 {
+        self.rotation.to_native()
+    }
+    pub fn irradiance(&self) -> Option<[[f32; 3]; 9]> {
+        self.irradiance
+            .as_ref()
+            .map(|sh| sh.each_ref().map(|c| c.each_ref().map(|x| x.to_native())))
+    }
+    pub fn specular(&self) -> Option<u32> {
+        self.specular.as_ref().map(|x| x.to_native())
+    }
+    #[cfg(feature = "safe")]
+    pub(crate) fn validate(&self, images: usize, errors: &mut Vec<ValidationError>) {
+        for image in [Some(self.image()), self.specular()].into_iter().flatten() {
+            if image as usize >= images {
+                errors.push(ValidationError::EnvironmentImageOutOfRange { image, images });
+            }
+        }
+    }
+}
+
+impl Environment {
+    pub const fn new(image: u32) -> Self {
+        Self {
+            image,
+            intensity: 1.0,
+            rotation: 0.0,
+            irradiance: None,
+            specular: None,
+            builder: None,
+        }
+    }
+    pub const fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+    pub const fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+    pub const fn image(&self) -> u32 {
+        self.image
+    }
+    pub(crate) const fn builder(&self) -> Option<u32> {
+        self.builder
+    }
+    pub const fn intensity(&self) -> f32 {
+        self.intensity
+    }
+    pub const fn rotation(&self) -> f32 {
+        self.rotation
+    }
+    pub const fn irradiance(&self) -> Option<&[[f32; 3]; 9]> {
+        self.irradiance.as_ref()
+    }
+    pub const fn specular(&self) -> Option<u32> {
+        self.specular
+    }
+    pub(crate) fn set_baked(&mut self, irradiance: [[f32; 3]; 9], specular: u32) {
+        self.irradiance = Some(irradiance);
+        self.specular = Some(specular);
+    }
+    // world direction to equirectangular uv, `rotation` included
+    pub fn uv(&self, direction: [f32; 3]) -> [f32; 2] {
+        ibl::uv(self.unrotate(direction))
+    }
+    // scaled by `intensity`, none until the environment is baked
+    pub fn irradiance_at(&self, normal: [f32; 3]) -> Option<[f32; 3]> {
+        let basis = ibl::sh_basis(self.unrotate(normal));
+        let sh = self.irradiance.as_ref()?;
+        Some(
+            [0, 1, 2]
+                .map(|c| sh.iter().zip(basis).map(|(s, b)| s[c] * b).sum::<f32>() * self.intensity),
+        )
+    }
+    fn unrotate(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        let (sin, cos) = (-self.rotation).sin_cos();
+        [cos * x + sin * z, y, -sin * x + cos * z]
+    }
+    pub(crate) fn validate(&self, images: usize, errors: &mut Vec<ValidationError>) {
+        for image in [Some(self.image), self.specular].into_iter().flatten() {
+            if image as usize >= images {
+                errors.push(ValidationError::EnvironmentImageOutOfRange { image, images });
+            }
+        }
+    }
+}
+
+impl From<ImageHandle> for Environment {
+    fn from(image: ImageHandle) -> Self {
+        Self {
+            builder: Some(image.builder()),
+            ..Self::new(image.index())
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{ImageFormat, LightUnit, Section};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    ImageOutOfRange {
+        material: usize,
+        image: u32,
+        images: usize,
+    },
+    MaterialOutOfRange {
+        mesh: usize,
+        material: u32,
+        materials: usize,
+    },
+    AttributeLenMismatch {
+        mesh: usize,
+    },
+    TangentSign {
+        mesh: usize,
+    },
+    ImageLayers {
+        image: usize,
+    },
+    CubeNotSquare {
+        image: usize,
+    },
+    LightDirection {
+        light: usize,
+    },
+    LightPower {
+        light: usize,
+    },
+    LightColor {
+        light: usize,
+    },
+    LightBlend {
+        light: usize,
+    },
+    LightAngle {
+        light: usize,
+    },
+    LightRange {
+        light: usize,
+    },
+    LightSize {
+        light: usize,
+    },
+    LightUnitMismatch {
+        light: usize,
+        unit: LightUnit,
+    },
+    EnvironmentImageOutOfRange {
+        image: u32,
+        images: usize,
+    },
+    LightmapImageOutOfRange {
+        mesh: usize,
+        image: u32,
+        images: usize,
+    },
+    LightmapUvSet {
+        mesh: usize,
+        uv_set: u32,
+    },
+    TextureUvSet {
+        mesh: usize,
+        material: u32,
+        uv_set: u32,
+    },
+    ProbeGrid {
+        volume: usize,
+    },
+    ProbeCount {
+        volume: usize,
+        expected: usize,
+        len: usize,
+    },
+    SectionCount {
+        section: Section,
+        expected: usize,
+        len: usize,
+    },
+    TooManyItems {
+        section: Section,
+        len: usize,
+    },
+    ForeignImage {
+        image: u32,
+    },
+    ForeignMaterial {
+        material: u32,
+    },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ImageOutOfRange {
+                material,
+                image,
+                images,
+            } => write!(
+                f,
+                "material {material} uses image {image} but scene has {images} images"
+            ),
+            Self::MaterialOutOfRange {
+                mesh,
+                material,
+                materials,
+            } => write!(
+                f,
+                "mesh {mesh} uses material {material} but scene has {materials} materials"
+            ),
+            Self::AttributeLenMismatch { mesh } => {
+                write!(f, "mesh {mesh} attributes DOESNT have the same len")
+            }
+            Self::TangentSign { mesh } => {
+                write!(f, "mesh {mesh} has tangents with w not equal to 1 or -1")
+            }
+            Self::ImageLayers { image } => {
+                write!(f, "image {image} levels don't split evenly into its layers")
+            }
+            Self::CubeNotSquare { image } => {
+                write!(f, "cube image {image} has faces that aren't square")
+            }
+            Self::LightDirection { light } => {
+                write!(f, "light {light} has a zero length or NaN direction")
+            }
+            Self::LightPower { light } => {
+                write!(f, "light {light} has a negative or NaN power")
+            }
+            Self::LightColor { light } => {
+                write!(f, "light {light} has a negative or NaN color")
+            }
+            Self::LightBlend { light } => {
+                write!(f, "light {light} has a spot blend outside 0..1")
+            }
+            Self::LightAngle { light } => {
+                write!(
+                    f,
+                    "light {light} has a spot angle that isn't above 0 and at most pi/2"
+                )
+            }
+            Self::LightRange { light } => {
+                write!(f, "light {light} has a zero, negative or NaN range")
+            }
+            Self::LightSize { light } => {
+                write!(
+                    f,
+                    "light {light} has a negative or NaN radius or a zero area size"
+                )
+            }
+            Self::LightUnitMismatch { light, unit } => {
+                write!(f, "light {light} can't use {unit:?} as its unit")
+            }
+            Self::EnvironmentImageOutOfRange { image, images } => write!(
+                f,
+                "environment uses image {image} but scene has {images} images"
+            ),
+            Self::LightmapImageOutOfRange {
+                mesh,
+                image,
+                images,
+            } => write!(
+                f,
+                "mesh {mesh} lightmap uses image {image} but scene has {images} images"
+            ),
+            Self::LightmapUvSet { mesh, uv_set } => {
+                write!(
+                    f,
+                    "mesh {mesh} lightmap uses uv set {uv_set} but mesh doesn't have it"
+                )
+            }
+            Self::TextureUvSet {
+                mesh,
+                material,
+                uv_set,
+            } => write!(
+                f,
+                "mesh {mesh} material {material} samples uv set {uv_set} but mesh doesn't have it"
+            ),
+            Self::ProbeGrid { volume } => write!(
+                f,
+                "probe volume {volume} has an empty resolution or invalid bounds"
+            ),
+            Self::ProbeCount {
+                volume,
+                expected,
+                len,
+            } => write!(
+                f,
+                "probe volume {volume} has {len} probes but its resolution needs {expected}"
+            ),
+            Self::SectionCount {
+                section,
+                expected,
+                len,
+            } => write!(
+                f,
+                "{section:?} section has {len} items but the table of contents says {expected}"
+            ),
+            Self::TooManyItems { section, len } => {
+                write!(
+                    f,
+                    "{section:?} section has {len} items, more than a u32 can count"
+                )
+            }
+            Self::ForeignImage { image } => {
+                write!(f, "image {image} wasn't added to this builder")
+            }
+            Self::ForeignMaterial { material } => {
+                write!(f, "material {material} wasn't added to this builder")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Rkyv(rkyv::rancor::Error),
+    Invalid(Vec<ValidationError>),
+    Qoi(qoi::Error),
+    Unsupported(ImageFormat),
+    ImageLength { len: usize, width: u32, height: u32 },
+    Corrupt,
+    NoLayers,
+    LayerMismatch { layer: usize },
+    NotEquirect,
+    BadMagic,
+    Version(u32),
+    Truncated,
+    HeaderChecksum,
+    // v1 files can't be checked before they're read
+    LegacyRequiresSafe,
+    Checksum { section: Section },
+    Codec { section: Section, codec: u32 },
+    Decompress { section: Section },
+    CompressionDisabled,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io: {e}"),
+            Self::Rkyv(e) => write!(f, "rkyv: {e}"),
+            Self::Invalid(errors) => {
+                write!(f, "invalid scene:")?;
+                for e in errors {
+                    write!(f, "\n  {e}")?;
+                }
+                Ok(())
+            }
+            Self::Qoi(e) => write!(f, "qoi: {e}"),
+            Self::Unsupported(format) => {
+                write!(f, "operation not supported for {format:?} images")
+            }
+            Self::ImageLength { len, width, height } => {
+                write!(f, "{len} values doesn't match a {width}x{height} image")
+            }
+            Self::Corrupt => write!(f, "corrupt data"),
+            Self::NoLayers => write!(f, "image needs at least one layer"),
+            Self::LayerMismatch { layer } => write!(
+                f,
+                "layer {layer} size, format or mip count doesn't match the first layer"
+            ),
+            Self::NotEquirect => write!(f, "environment image must be a 2D equirect image"),
+            Self::BadMagic => write!(f, "not a rnm file"),
+            Self::Version(version) => write!(f, "unsupported file version {version}"),
+            Self::Truncated => write!(f, "file is truncated"),
+            Self::HeaderChecksum => write!(f, "header checksum mismatch"),
+            Self::LegacyRequiresSafe => {
+                write!(f, "v1 files can only be loaded with the safe feature")
+            }
+            Self::Checksum { section } => write!(f, "{section:?} section checksum mismatch"),
+            Self::Codec { section, codec } => {
+                write!(f, "{section:?} section uses unavailable codec {codec}")
+            }
+            Self::Decompress { section } => write!(f, "{section:?} section failed to decompress"),
+            Self::CompressionDisabled => write!(f, "compression feature is disabled"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<qoi::Error> for Error {
+    fn from(e: qoi::Error) -> Self {
+        Self::Qoi(e)
+    }
+}
+
+impl From<rkyv::rancor::Error> for Error {
+    fn from(e: rkyv::rancor::Error) -> Self {
+        Self::Rkyv(e)
+    }
+}
@@ -1,56 +1,41 @@
-#[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+use rkyv::util::AlignedVec;
+
+use crate::Error;
+
+// the v1 container: a single rkyv blob compressed as a whole, only read to
+// migrate old files
+const MAGIC_V1: &str = "RNM";
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
 pub(crate) enum FileData {
     Compressed(Vec<u8>),
     Uncompressed(Vec<u8>),
 }
 
-#[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+#[derive(rkyv::Archive, rkyv::Deserialize)]
 pub(crate) struct File {
     magic: String,
     data: FileData,
 }
 
 impl File {
-    pub(crate) fn save(bytes: &[u8]) -> Self {
-        #[cfg(feature = "compression")]
-        {
-            let c = lz4_flex::block::compress_prepend_size(bytes);
-            if c.len() < bytes.len() {
-                Self {
-                    magic: "RNM".into(),
-                    data: FileData::Compressed(c),
-                }
-            } else {
-                Self {
-                    magic: "RNM".into(),
-                    data: FileData::Uncompressed(bytes.to_vec()),
-                }
-            }
+    pub(crate) fn load(data: &[u8]) -> Result<AlignedVec<16>, Error> {
+        let mut bytes = AlignedVec::<16>::new();
+        bytes.extend_from_slice(data);
+        let file = rkyv::access::<ArchivedFile, rkyv::rancor::Error>(&bytes)?;
+        if file.magic != MAGIC_V1 {
+            return Err(Error::BadMagic);
         }
-        #[cfg(not(feature = "compression"))]
-        {
-            Self {
-                magic: "RNM".into(),
-                data: FileData::Uncompressed(bytes.to_vec()),
-            }
-        }
-    }
-    pub(crate) fn load(file: &ArchivedFile) -> Option<Vec<u8>> {
-        if file.magic != "RNM" {
-            None
-        } else {
-            match &file.data {
-                #[cfg(feature = "compression")]
-                ArchivedFileData::Compressed(x) => {
-                    lz4_flex::block::decompress_size_prepended(&x).ok()
-                }
-                #[cfg(not(feature = "compression"))]
-                ArchivedFileData::Compressed(_) => {
-                    println!("[ERROR]: compression feature is disabled!");
-                    None
-                }
-                ArchivedFileData::Uncompressed(x) => Some(x.to_vec()),
-            }
+        let mut out = AlignedVec::new();
+        match &file.data {
+            #[cfg(feature = "compression")]
+            ArchivedFileData::Compressed(x) => out.extend_from_slice(
+                &lz4_flex::block::decompress_size_prepended(x).map_err(|_| Error::Corrupt)?,
+            ),
+            #[cfg(not(feature = "compression"))]
+            ArchivedFileData::Compressed(_) => return Err(Error::CompressionDisabled),
+            ArchivedFileData::Uncompressed(x) => out.extend_from_slice(x),
         }
+        Ok(out)
     }
 }
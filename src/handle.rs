@@ -0,0 +1,36 @@
+// `builder` is the id of the `SceneBuilder` that made the handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHandle {
+    builder: u32,
+    index: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialHandle {
+    builder: u32,
+    index: u32,
+}
+
+impl ImageHandle {
+    pub(crate) const fn new(builder: u32, index: u32) -> Self {
+        Self { builder, index }
+    }
+    pub const fn index(self) -> u32 {
+        self.index
+    }
+    pub(crate) const fn builder(self) -> u32 {
+        self.builder
+    }
+}
+
+impl MaterialHandle {
+    pub(crate) const fn new(builder: u32, index: u32) -> Self {
+        Self { builder, index }
+    }
+    pub const fn index(self) -> u32 {
+        self.index
+    }
+    pub(crate) const fn builder(self) -> u32 {
+        self.builder
+    }
+}
@@ -0,0 +1,217 @@
+use std::f32::consts::PI;
+
+use crate::mip::{MipFilter, Surface};
+
+// equirectangular layout: u = 0.5 looks down -Z, v = 0 is +Y
+pub(crate) fn direction(u: f32, v: f32) -> [f32; 3] {
+    let (phi, theta) = ((u - 0.5) * 2.0 * PI, v * PI);
+    [
+        theta.sin() * phi.sin(),
+        theta.cos(),
+        -theta.sin() * phi.cos(),
+    ]
+}
+
+pub(crate) fn uv(d: [f32; 3]) -> [f32; 2] {
+    let len = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+    let phi = d[0].atan2(-d[2]);
+    let theta = (d[1] / len).clamp(-1.0, 1.0).acos();
+    [phi / (2.0 * PI) + 0.5, theta / PI]
+}
+
+// real spherical harmonics up to l = 2 in the usual (l, m) order
+pub(crate) fn sh_basis([x, y, z]: [f32; 3]) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * y,
+        0.488_603 * z,
+        0.488_603 * x,
+        1.092_548 * x * y,
+        1.092_548 * y * z,
+        0.315_392 * (3.0 * z * z - 1.0),
+        1.092_548 * x * z,
+        0.546_274 * (x * x - y * y),
+    ]
+}
+
+// projects the radiance and convolves it with the clamped cosine (Ramamoorthi
+// and Hanrahan), so evaluating the basis gives irradiance directly
+pub(crate) fn irradiance_sh(source: &Surface) -> [[f32; 3]; 9] {
+    let (w, h) = (source.width, source.height);
+    let mut sh = [[0.0; 3]; 9];
+    for y in 0..h {
+        let v = (y as f32 + 0.5) / h as f32;
+        let solid_angle = (2.0 * PI / w as f32) * (PI / h as f32) * (v * PI).sin();
+        for x in 0..w {
+            let basis = sh_basis(direction((x as f32 + 0.5) / w as f32, v));
+            let px = &source.data[(y * w + x) * source.channels..];
+            for (coeff, b) in sh.iter_mut().zip(basis) {
+                for c in 0..3 {
+                    coeff[c] += px[c] * b * solid_angle;
+                }
+            }
+        }
+    }
+    let band = [PI, 2.0 * PI / 3.0, 2.0 * PI / 3.0, 2.0 * PI / 3.0];
+    for (i, coeff) in sh.iter_mut().enumerate() {
+        let a = band.get(i).copied().unwrap_or(PI / 4.0);
+        *coeff = coeff.map(|c| c * a);
+    }
+    sh
+}
+
+fn bilinear(s: &Surface, [u, v]: [f32; 2]) -> [f32; 3] {
+    let x = u * s.width as f32 - 0.5;
+    let y = (v * s.height as f32 - 0.5).clamp(0.0, (s.height - 1) as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let w = s.width as isize;
+    // u wraps around, v is clamped at the poles
+    let px = |x: isize, y: usize| {
+        let i = (y * s.width + x.rem_euclid(w) as usize) * s.channels;
+        [s.data[i], s.data[i + 1], s.data[i + 2]]
+    };
+    let (x0, y0) = (x0 as isize, y0 as usize);
+    let y1 = (y0 + 1).min(s.height - 1);
+    let (a, b, c, d) = (px(x0, y0), px(x0 + 1, y0), px(x0, y1), px(x0 + 1, y1));
+    [0, 1, 2].map(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        top + (bottom - top) * fy
+    })
+}
+
+// rows are weighted by their solid angle, a plain box filter would smear
+// the tiny polar texels over the whole top and bottom rows
+fn downsample(s: &Surface) -> Surface {
+    let row = |y: usize| ((y as f32 + 0.5) / s.height as f32 * PI).sin();
+    let mut data = s.data.clone();
+    for (y, px) in data.chunks_mut(s.width * s.channels).enumerate() {
+        px.iter_mut().for_each(|c| *c *= row(y));
+    }
+    let weights = (0..s.height)
+        .flat_map(|y| std::iter::repeat_n(row(y), s.width))
+        .collect();
+    let box_filter = |channels, data| {
+        Surface {
+            width: s.width,
+            height: s.height,
+            channels,
+            data,
+        }
+        .downsample(MipFilter::Box)
+    };
+    let mut out = box_filter(s.channels, data);
+    let weights = box_filter(1, weights);
+    for (px, w) in out.data.chunks_mut(s.channels).zip(weights.data) {
+        px.iter_mut().for_each(|c| *c /= w.max(f32::EPSILON));
+    }
+    out
+}
+
+fn sample(chain: &[Surface], uv: [f32; 2], lod: f32) -> [f32; 3] {
+    let lod = lod.clamp(0.0, (chain.len() - 1) as f32);
+    let (l, t) = (lod.floor() as usize, lod.fract());
+    let a = bilinear(&chain[l], uv);
+    if t == 0.0 || l + 1 >= chain.len() {
+        return a;
+    }
+    let b = bilinear(&chain[l + 1], uv);
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+fn hammersley(i: u32, n: u32) -> [f32; 2] {
+    [
+        i as f32 / n as f32,
+        i.reverse_bits() as f32 * 2.328_306_4e-10,
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    v.map(|x| x / len)
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// GGX importance sampling with N = V = R and the sample lod picked from its
+// pdf (Karis 2013, filtered importance sampling) to avoid fireflies
+fn prefilter_texel(chain: &[Surface], n: [f32; 3], roughness: f32, samples: u32) -> [f32; 3] {
+    let a2 = roughness.powi(4);
+    let up = if n[2].abs() < 0.999 {
+        [0.0, 0.0, 1.0]
+    } else {
+        [1.0, 0.0, 0.0]
+    };
+    let tx = normalize(cross(up, n));
+    let ty = cross(n, tx);
+    let texel = 4.0 * PI / (chain[0].width * chain[0].height) as f32;
+    let (mut color, mut weight) = ([0.0; 3], 0.0);
+    for i in 0..samples {
+        let [e0, e1] = hammersley(i, samples);
+        let phi = 2.0 * PI * e0;
+        let cos = ((1.0 - e1) / (1.0 + (a2 - 1.0) * e1)).sqrt();
+        let sin = (1.0 - cos * cos).sqrt();
+        let t = [sin * phi.cos(), sin * phi.sin(), cos];
+        let h = [0, 1, 2].map(|k| tx[k] * t[0] + ty[k] * t[1] + n[k] * t[2]);
+        let l = [0, 1, 2].map(|k| 2.0 * cos * h[k] - n[k]);
+        let nl = n[0] * l[0] + n[1] * l[1] + n[2] * l[2];
+        if nl <= 0.0 {
+            continue;
+        }
+        let d = a2 / (PI * (cos * cos * (a2 - 1.0) + 1.0).powi(2));
+        // pdf = D * NoH / (4 * VoH), and VoH = NoH here
+        let pdf = d / 4.0;
+        let lod = 0.5 * (1.0 / (samples as f32 * pdf * texel)).log2() + 1.0;
+        let c = sample(chain, uv(l), lod);
+        for k in 0..3 {
+            color[k] += c[k] * nl;
+        }
+        weight += nl;
+    }
+    color.map(|c| c / weight.max(f32::EPSILON))
+}
+
+// equirectangular mip chain `width` wide, level i is prefiltered for
+// roughness i / (levels - 1)
+pub(crate) fn prefilter(source: Surface, width: usize, samples: u32) -> Vec<Surface> {
+    let mut chain = vec![source];
+    while let Some(last) = chain.last().filter(|s| s.width > 1 || s.height > 1) {
+        let next = downsample(last);
+        chain.push(next);
+    }
+    let width = width.max(1);
+    let levels = width.ilog2() as usize + 1;
+    let mut out = Vec::with_capacity(levels);
+    for level in 0..levels {
+        let (w, h) = ((width >> level).max(1), (width >> (level + 1)).max(1));
+        let roughness = level as f32 / (levels - 1).max(1) as f32;
+        // a mirror only needs the source resampled to this size
+        let base = (chain[0].width as f32 / w as f32).log2().max(0.0);
+        let mut data = Vec::with_capacity(w * h * 3);
+        for y in 0..h {
+            for x in 0..w {
+                let uv = [(x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32];
+                let c = if level == 0 {
+                    sample(&chain, uv, base)
+                } else {
+                    prefilter_texel(&chain, direction(uv[0], uv[1]), roughness, samples)
+                };
+                data.extend(c);
+            }
+        }
+        out.push(Surface {
+            width: w,
+            height: h,
+            channels: 3,
+            data,
+        });
+    }
+    out
+}
@@ -1,30 +1,306 @@
+use crate::{
+    bcn,
+    mip::{MipFilter, Surface},
+    rgbe, ColorSpace, Error, ImageFormat, ImageKind, ValidationError,
+};
+
+// byte offset of the colorspace flag in the QOI header
+const QOI_COLORSPACE: usize = 13;
+
 #[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub struct Image {
     width: u32,
     height: u32,
-    data: Vec<u8>,
+    color_space: ColorSpace,
+    format: ImageFormat,
+    kind: ImageKind,
+    // mip chains encoded as `format`, one full chain per layer (layer-major),
+    // level 0 is the full size image
+    levels: Vec<Vec<u8>>,
+}
+
+fn level_size(width: u32, height: u32, level: usize) -> (u32, u32) {
+    let shift = if level < 32 { level as u32 } else { 31 };
+    ((width >> shift).max(1), (height >> shift).max(1))
+}
+
+fn decode_qoi(format: ImageFormat, data: Option<&[u8]>, rgba: bool) -> Result<Vec<u8>, Error> {
+    if format != ImageFormat::Qoi {
+        return Err(Error::Unsupported(format));
+    }
+    let decoder = qoi::Decoder::new(data.unwrap_or_default())?;
+    let mut decoder = if rgba {
+        decoder.with_channels(qoi::Channels::Rgba)
+    } else {
+        decoder
+    };
+    Ok(decoder.decode_to_vec()?)
+}
+
+fn decode_qoi_into(
+    format: ImageFormat,
+    data: Option<&[u8]>,
+    rgba: bool,
+    buf: &mut [u8],
+) -> Result<usize, Error> {
+    if format != ImageFormat::Qoi {
+        return Err(Error::Unsupported(format));
+    }
+    let decoder = qoi::Decoder::new(data.unwrap_or_default())?;
+    let mut decoder = if rgba {
+        decoder.with_channels(qoi::Channels::Rgba)
+    } else {
+        decoder
+    };
+    Ok(decoder.decode_to_buf(buf)?)
+}
+
+// channels of the decoded pixels, 0 for a broken QOI header
+fn channels(format: ImageFormat, data: Option<&[u8]>) -> u8 {
+    match format {
+        ImageFormat::Qoi => qoi::decode_header(data.unwrap_or_default())
+            .map(|h| h.channels.as_u8())
+            .unwrap_or(0),
+        ImageFormat::Bc5 => 2,
+        ImageFormat::Rgbe | ImageFormat::RgbeLz4 => 3,
+        ImageFormat::Bc1 | ImageFormat::Bc3 | ImageFormat::Bc7 => 4,
+    }
+}
+
+fn decode_rgbe(
+    format: ImageFormat,
+    data: Option<&[u8]>,
+    (width, height): (u32, u32),
+) -> Result<Vec<f32>, Error> {
+    let data = data.ok_or(Error::Corrupt)?;
+    let data = match format {
+        ImageFormat::Rgbe => data.to_vec(),
+        #[cfg(feature = "compression")]
+        ImageFormat::RgbeLz4 => {
+            lz4_flex::block::decompress_size_prepended(data).map_err(|_| Error::Corrupt)?
+        }
+        format => return Err(Error::Unsupported(format)),
+    };
+    if data.len() != width as usize * height as usize * 4 {
+        return Err(Error::Corrupt);
+    }
+    Ok(rgbe::decode(&data))
+}
+
+// lazy access straight from the loaded buffer, nothing is decoded up front
+impl ArchivedImage {
+    pub const fn width(&self) -> u32 {
+        self.width.to_native()
+    }
+    pub const fn height(&self) -> u32 {
+        self.height.to_native()
+    }
+    pub fn color_space(&self) -> ColorSpace {
+        (&self.color_space).into()
+    }
+    pub fn format(&self) -> ImageFormat {
+        (&self.format).into()
+    }
+    pub fn kind(&self) -> ImageKind {
+        (&self.kind).into()
+    }
+    pub const fn layer_count(&self) -> usize {
+        self.kind.layers()
+    }
+    pub fn level_count(&self) -> usize {
+        self.levels.len() / self.layer_count().max(1)
+    }
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        level_size(self.width(), self.height(), level)
+    }
+    pub fn channels(&self) -> u8 {
+        channels(self.format(), self.level(0))
+    }
+    pub fn decoded_len(&self) -> usize {
+        self.width() as usize * self.height() as usize * self.channels() as usize
+    }
+    pub fn level(&self, level: usize) -> Option<&[u8]> {
+        self.layer_level(0, level)
+    }
+    pub fn layer_level(&self, layer: usize, level: usize) -> Option<&[u8]> {
+        let levels = self.level_count();
+        if layer >= self.layer_count() || level >= levels {
+            return None;
+        }
+        self.levels
+            .get(layer * levels + level)
+            .map(|x| x.as_slice())
+    }
+    pub fn decode(&self) -> Result<Vec<u8>, Error> {
+        self.decode_layer(0, 0)
+    }
+    pub fn decode_rgba8(&self) -> Result<Vec<u8>, Error> {
+        self.decode_layer_rgba8(0, 0)
+    }
+    pub fn decode_layer(&self, layer: usize, level: usize) -> Result<Vec<u8>, Error> {
+        decode_qoi(self.format(), self.layer_level(layer, level), false)
+    }
+    pub fn decode_layer_rgba8(&self, layer: usize, level: usize) -> Result<Vec<u8>, Error> {
+        decode_qoi(self.format(), self.layer_level(layer, level), true)
+    }
+    pub fn decode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        decode_qoi_into(self.format(), self.level(0), false, buf)
+    }
+    pub fn decode_rgba8_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        decode_qoi_into(self.format(), self.level(0), true, buf)
+    }
+    pub fn decode_hdr(&self) -> Result<Vec<f32>, Error> {
+        self.decode_layer_hdr(0, 0)
+    }
+    pub fn decode_layer_hdr(&self, layer: usize, level: usize) -> Result<Vec<f32>, Error> {
+        decode_rgbe(
+            self.format(),
+            self.layer_level(layer, level),
+            self.level_size(level),
+        )
+    }
+    #[cfg(feature = "safe")]
+    pub(crate) fn validate(&self, image: usize, errors: &mut Vec<ValidationError>) {
+        if self.levels.is_empty() || !self.levels.len().is_multiple_of(self.kind.layers()) {
+            errors.push(ValidationError::ImageLayers { image });
+        }
+        if self.kind() == ImageKind::Cube && self.width() != self.height() {
+            errors.push(ValidationError::CubeNotSquare { image });
+        }
+    }
 }
 
 impl std::fmt::Debug for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("Image: [{}x{}]", self.width, self.height))
+        f.write_fmt(format_args!(
+            "Image: [{}x{} {:?} {:?} {:?} {} levels]",
+            self.width,
+            self.height,
+            self.kind,
+            self.format,
+            self.color_space,
+            self.level_count()
+        ))
     }
 }
 
 impl Image {
     pub fn from_raw(width: u32, height: u32, data: &[u8]) -> Result<Self, qoi::Error> {
-        let data = qoi::encode_to_vec(data, width, height)?;
+        Self::from_raw_with(width, height, data, ColorSpace::Srgb)
+    }
+    pub fn from_raw_with(
+        width: u32,
+        height: u32,
+        data: &[u8],
+        color_space: ColorSpace,
+    ) -> Result<Self, qoi::Error> {
+        let data = qoi::Encoder::new(data, width, height)?
+            .with_colorspace(color_space.into())
+            .encode_to_vec()?;
         Ok(Self {
             width,
             height,
-            data,
+            color_space,
+            format: ImageFormat::Qoi,
+            kind: ImageKind::D2,
+            levels: vec![data],
         })
     }
-    pub const fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+        let color_space = qoi::decode_header(&data)
+            .map(|h| h.colorspace.into())
+            .unwrap_or_default();
         Self {
             width,
             height,
-            data,
+            color_space,
+            format: ImageFormat::Qoi,
+            kind: ImageKind::D2,
+            levels: vec![data],
+        }
+    }
+    // `data` is RGB or RGBA f32 in linear space, alpha is dropped
+    pub fn from_hdr(width: u32, height: u32, data: &[f32]) -> Result<Self, Error> {
+        let pixels = width as usize * height as usize;
+        let channels = data.len().checked_div(pixels).unwrap_or_default();
+        if !matches!(channels, 3 | 4) || data.len() != pixels * channels {
+            return Err(Error::ImageLength {
+                len: data.len(),
+                width,
+                height,
+            });
+        }
+        let rgb = data
+            .chunks_exact(channels)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect::<Vec<_>>();
+        #[cfg(feature = "compression")]
+        let format = ImageFormat::RgbeLz4;
+        #[cfg(not(feature = "compression"))]
+        let format = ImageFormat::Rgbe;
+        Ok(Self {
+            width,
+            height,
+            color_space: ColorSpace::Linear,
+            format,
+            kind: ImageKind::D2,
+            levels: vec![Self::pack_hdr(format, &rgb)],
+        })
+    }
+    pub fn cube(faces: [Image; 6]) -> Result<Self, Error> {
+        Self::stack(ImageKind::Cube, faces.into())
+    }
+    pub fn array(layers: Vec<Image>) -> Result<Self, Error> {
+        let Ok(count) = u32::try_from(layers.len()) else {
+            return Err(Error::Invalid(vec![ValidationError::ImageLayers {
+                image: 0,
+            }]));
+        };
+        Self::stack(ImageKind::Array { layers: count }, layers)
+    }
+    // every layer must be a 2D image with the same size, format and mip count
+    fn stack(kind: ImageKind, layers: Vec<Image>) -> Result<Self, Error> {
+        let first = layers.first().ok_or(Error::NoLayers)?;
+        let (width, height, color_space, format, levels) = (
+            first.width,
+            first.height,
+            first.color_space,
+            first.format,
+            first.levels.len(),
+        );
+        for (i, layer) in layers.iter().enumerate() {
+            if layer.kind != ImageKind::D2
+                || layer.width != width
+                || layer.height != height
+                || layer.format != format
+                || layer.levels.len() != levels
+            {
+                return Err(Error::LayerMismatch { layer: i });
+            }
+        }
+        let mut image = Self {
+            width,
+            height,
+            color_space,
+            format,
+            kind,
+            levels: layers.into_iter().flat_map(|l| l.levels).collect(),
+        };
+        image.set_color_space(color_space);
+        // not part of a scene yet, so errors are reported as image 0
+        let mut errors = Vec::new();
+        image.validate(0, &mut errors);
+        if !errors.is_empty() {
+            return Err(Error::Invalid(errors));
+        }
+        Ok(image)
+    }
+    fn pack_hdr(format: ImageFormat, rgb: &[f32]) -> Vec<u8> {
+        let data = rgbe::encode(rgb);
+        match format {
+            #[cfg(feature = "compression")]
+            ImageFormat::RgbeLz4 => lz4_flex::block::compress_prepend_size(&data),
+            _ => data,
         }
     }
     pub const fn width(&self) -> u32 {
@@ -33,10 +309,224 @@ impl Image {
     pub const fn height(&self) -> u32 {
         self.height
     }
+    pub const fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+    pub const fn format(&self) -> ImageFormat {
+        self.format
+    }
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        // HDR data is always linear
+        if self.format.is_hdr() {
+            return;
+        }
+        self.color_space = color_space;
+        if self.format.is_block_compressed() {
+            return;
+        }
+        for level in &mut self.levels {
+            if let Some(flag) = level.get_mut(QOI_COLORSPACE) {
+                *flag = qoi::ColorSpace::from(color_space).as_u8();
+            }
+        }
+    }
+    pub const fn kind(&self) -> ImageKind {
+        self.kind
+    }
+    pub const fn layer_count(&self) -> usize {
+        self.kind.layers()
+    }
     pub fn data(&self) -> &[u8] {
-        &self.data
+        self.level(0).unwrap_or_default()
+    }
+    pub fn level_count(&self) -> usize {
+        self.levels.len() / self.layer_count().max(1)
+    }
+    pub fn level(&self, level: usize) -> Option<&[u8]> {
+        self.layer_level(0, level)
+    }
+    pub fn layer_level(&self, layer: usize, level: usize) -> Option<&[u8]> {
+        let levels = self.level_count();
+        if layer >= self.layer_count() || level >= levels {
+            return None;
+        }
+        self.levels.get(layer * levels + level).map(Vec::as_slice)
+    }
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        level_size(self.width, self.height, level)
+    }
+    pub fn channels(&self) -> u8 {
+        channels(self.format, self.level(0))
+    }
+    pub fn decoded_len(&self) -> usize {
+        self.width as usize * self.height as usize * self.channels() as usize
+    }
+    fn qoi_level(&self, layer: usize, level: usize) -> Result<&[u8], Error> {
+        match self.format {
+            ImageFormat::Qoi => Ok(self.layer_level(layer, level).unwrap_or_default()),
+            format => Err(Error::Unsupported(format)),
+        }
+    }
+    pub fn decode(&self) -> Result<Vec<u8>, Error> {
+        self.decode_level(0)
+    }
+    pub fn decode_rgba8(&self) -> Result<Vec<u8>, Error> {
+        self.decode_level_rgba8(0)
     }
-    pub fn decode(&self) -> Result<Vec<u8>, qoi::Error> {
-        qoi::decode_to_vec(&self.data).map(|x| x.1)
+    pub fn decode_level(&self, level: usize) -> Result<Vec<u8>, Error> {
+        self.decode_layer(0, level)
+    }
+    pub fn decode_level_rgba8(&self, level: usize) -> Result<Vec<u8>, Error> {
+        self.decode_layer_rgba8(0, level)
+    }
+    pub fn decode_layer(&self, layer: usize, level: usize) -> Result<Vec<u8>, Error> {
+        decode_qoi(self.format, self.layer_level(layer, level), false)
+    }
+    pub fn decode_layer_rgba8(&self, layer: usize, level: usize) -> Result<Vec<u8>, Error> {
+        decode_qoi(self.format, self.layer_level(layer, level), true)
+    }
+    pub fn decode_hdr(&self) -> Result<Vec<f32>, Error> {
+        self.decode_level_hdr(0)
+    }
+    pub fn decode_level_hdr(&self, level: usize) -> Result<Vec<f32>, Error> {
+        self.decode_layer_hdr(0, level)
+    }
+    pub fn decode_layer_hdr(&self, layer: usize, level: usize) -> Result<Vec<f32>, Error> {
+        decode_rgbe(
+            self.format,
+            self.layer_level(layer, level),
+            self.level_size(level),
+        )
+    }
+    pub fn decode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        decode_qoi_into(self.format, self.level(0), false, buf)
+    }
+    pub fn decode_rgba8_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        decode_qoi_into(self.format, self.level(0), true, buf)
+    }
+    // replaces any existing chain, sRGB images are filtered in linear space
+    pub fn generate_mips(&mut self, filter: MipFilter) -> Result<(), Error> {
+        self.build_mips(filter, false)
+    }
+    // keeps the filtered normals unit length, the image is treated as linear
+    pub fn generate_normal_mips(&mut self, filter: MipFilter) -> Result<(), Error> {
+        self.build_mips(filter, true)
+    }
+    fn build_mips(&mut self, filter: MipFilter, normal: bool) -> Result<(), Error> {
+        let mut levels = Vec::new();
+        for layer in 0..self.layer_count() {
+            let chain = self.mip_chain(layer, filter, normal)?;
+            levels.push(self.layer_level(layer, 0).unwrap_or_default().to_vec());
+            levels.extend(chain);
+        }
+        self.levels = levels;
+        Ok(())
+    }
+    // level 0 of `layer` as floats, linearized when `srgb` is set
+    pub(crate) fn surface(&self, layer: usize, srgb: bool) -> Result<Surface, Error> {
+        if self.format.is_hdr() {
+            let (width, height) = self.level_size(0);
+            return Ok(Surface {
+                width: width as usize,
+                height: height as usize,
+                channels: 3,
+                data: self.decode_layer_hdr(layer, 0)?,
+            });
+        }
+        let (header, pixels) = qoi::decode_to_vec(self.qoi_level(layer, 0)?)?;
+        Ok(Surface::from_u8(
+            header.width as usize,
+            header.height as usize,
+            header.channels.as_u8() as usize,
+            &pixels,
+            srgb,
+        ))
+    }
+    // HDR image from a prebuilt RGB mip chain
+    pub(crate) fn from_hdr_levels(levels: &[Surface]) -> Self {
+        #[cfg(feature = "compression")]
+        let format = ImageFormat::RgbeLz4;
+        #[cfg(not(feature = "compression"))]
+        let format = ImageFormat::Rgbe;
+        let (width, height) = levels.first().map_or((1, 1), |l| (l.width, l.height));
+        Self {
+            width: width as u32,
+            height: height as u32,
+            color_space: ColorSpace::Linear,
+            format,
+            kind: ImageKind::D2,
+            levels: levels
+                .iter()
+                .map(|l| Self::pack_hdr(format, &l.data))
+                .collect(),
+        }
+    }
+    fn mip_chain(
+        &self,
+        layer: usize,
+        filter: MipFilter,
+        normal: bool,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let hdr = self.format.is_hdr();
+        let srgb = !normal && self.color_space == ColorSpace::Srgb;
+        let mut surface = self.surface(layer, srgb)?;
+        let mut chain = Vec::new();
+        while surface.width > 1 || surface.height > 1 {
+            surface = surface.downsample(filter);
+            if hdr {
+                chain.push(Self::pack_hdr(self.format, &surface.data));
+                continue;
+            }
+            if normal {
+                surface.renormalize();
+            }
+            let level = qoi::Encoder::new(
+                &surface.to_u8(srgb),
+                surface.width as u32,
+                surface.height as u32,
+            )?
+            .with_colorspace(self.color_space.into())
+            .encode_to_vec()?;
+            chain.push(level);
+        }
+        Ok(chain)
+    }
+    // block compresses every level on the CPU, generate mips first
+    pub fn compress(&mut self, format: ImageFormat) -> Result<(), Error> {
+        if format == self.format {
+            return Ok(());
+        }
+        if !format.is_block_compressed() {
+            return Err(Error::Unsupported(format));
+        }
+        if self.format != ImageFormat::Qoi {
+            return Err(Error::Unsupported(self.format));
+        }
+        let levels = self.level_count();
+        self.levels = (0..self.levels.len())
+            .map(|i| {
+                let (width, height) = self.level_size(i % levels);
+                let rgba = self.decode_layer_rgba8(i / levels, i % levels)?;
+                // the qoi stream carries its own size, which `Image::new` doesn't check
+                if rgba.len() != width as usize * height as usize * 4 {
+                    return Err(Error::ImageLength {
+                        len: rgba.len(),
+                        width,
+                        height,
+                    });
+                }
+                Ok(bcn::encode(format, width as usize, height as usize, &rgba))
+            })
+            .collect::<Result<_, Error>>()?;
+        self.format = format;
+        Ok(())
+    }
+    pub(crate) fn validate(&self, image: usize, errors: &mut Vec<ValidationError>) {
+        if self.levels.is_empty() || !self.levels.len().is_multiple_of(self.layer_count()) {
+            errors.push(ValidationError::ImageLayers { image });
+        }
+        if self.kind() == ImageKind::Cube && self.width() != self.height() {
+            errors.push(ValidationError::CubeNotSquare { image });
+        }
     }
 }
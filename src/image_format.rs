@@ -0,0 +1,45 @@
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum ImageFormat {
+    #[default]
+    Qoi,
+    // block compressed, 4x4 blocks in row-major order
+    Bc1,
+    Bc3,
+    Bc5,
+    Bc7,
+    // shared exponent HDR, always linear
+    Rgbe,
+    RgbeLz4,
+}
+
+impl ImageFormat {
+    pub const fn is_block_compressed(&self) -> bool {
+        matches!(self, Self::Bc1 | Self::Bc3 | Self::Bc5 | Self::Bc7)
+    }
+    pub const fn is_hdr(&self) -> bool {
+        matches!(self, Self::Rgbe | Self::RgbeLz4)
+    }
+    pub const fn block_size(&self) -> Option<usize> {
+        match self {
+            Self::Qoi | Self::Rgbe | Self::RgbeLz4 => None,
+            Self::Bc1 => Some(8),
+            Self::Bc3 | Self::Bc5 | Self::Bc7 => Some(16),
+        }
+    }
+}
+
+impl From<&ArchivedImageFormat> for ImageFormat {
+    fn from(f: &ArchivedImageFormat) -> Self {
+        match f {
+            ArchivedImageFormat::Qoi => Self::Qoi,
+            ArchivedImageFormat::Bc1 => Self::Bc1,
+            ArchivedImageFormat::Bc3 => Self::Bc3,
+            ArchivedImageFormat::Bc5 => Self::Bc5,
+            ArchivedImageFormat::Bc7 => Self::Bc7,
+            ArchivedImageFormat::Rgbe => Self::Rgbe,
+            ArchivedImageFormat::RgbeLz4 => Self::RgbeLz4,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum ImageKind {
+    #[default]
+    D2,
+    // faces in +X, -X, +Y, -Y, +Z, -Z order
+    Cube,
+    Array {
+        layers: u32,
+    },
+}
+
+impl ArchivedImageKind {
+    pub(crate) const fn layers(&self) -> usize {
+        match self {
+            Self::D2 => 1,
+            Self::Cube => 6,
+            Self::Array { layers } => layers.to_native() as usize,
+        }
+    }
+}
+
+impl ImageKind {
+    pub const fn layers(&self) -> usize {
+        match self {
+            Self::D2 => 1,
+            Self::Cube => 6,
+            Self::Array { layers } => *layers as usize,
+        }
+    }
+}
+
+impl From<&ArchivedImageKind> for ImageKind {
+    fn from(k: &ArchivedImageKind) -> Self {
+        match k {
+            ArchivedImageKind::D2 => Self::D2,
+            ArchivedImageKind::Cube => Self::Cube,
+            ArchivedImageKind::Array { layers } => Self::Array {
+                layers: layers.to_native(),
+            },
+        }
+    }
+}
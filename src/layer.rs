@@ -0,0 +1,79 @@
+use crate::{texture_ref::ArchivedTextureRef, TextureRef};
+
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub enum MaterialLayer {
+    Clearcoat {
+        factor: f32,
+        texture: Option<TextureRef>,
+        roughness: f32,
+        roughness_texture: Option<TextureRef>,
+        normal_texture: Option<TextureRef>,
+    },
+    Transmission {
+        factor: f32,
+        texture: Option<TextureRef>,
+    },
+    Sheen {
+        color: [f32; 3],
+        color_texture: Option<TextureRef>,
+        roughness: f32,
+        roughness_texture: Option<TextureRef>,
+    },
+    Ior(f32),
+    Unlit,
+}
+
+impl ArchivedMaterialLayer {
+    pub(crate) fn textures(&self) -> [Option<&ArchivedTextureRef>; 3] {
+        let image = rkyv::option::ArchivedOption::<ArchivedTextureRef>::as_ref;
+        match self {
+            Self::Clearcoat {
+                texture,
+                roughness_texture,
+                normal_texture,
+                ..
+            } => [
+                image(texture),
+                image(roughness_texture),
+                image(normal_texture),
+            ],
+            Self::Transmission { texture, .. } => [image(texture), None, None],
+            Self::Sheen {
+                color_texture,
+                roughness_texture,
+                ..
+            } => [image(color_texture), image(roughness_texture), None],
+            Self::Ior(_) | Self::Unlit => [None; 3],
+        }
+    }
+}
+
+impl MaterialLayer {
+    pub(crate) fn color_texture(&self) -> Option<&TextureRef> {
+        match self {
+            Self::Sheen { color_texture, .. } => color_texture.as_ref(),
+            _ => None,
+        }
+    }
+    pub fn textures(&self) -> [Option<&TextureRef>; 3] {
+        match self {
+            Self::Clearcoat {
+                texture,
+                roughness_texture,
+                normal_texture,
+                ..
+            } => [
+                texture.as_ref(),
+                roughness_texture.as_ref(),
+                normal_texture.as_ref(),
+            ],
+            Self::Transmission { texture, .. } => [texture.as_ref(), None, None],
+            Self::Sheen {
+                color_texture,
+                roughness_texture,
+                ..
+            } => [color_texture.as_ref(), roughness_texture.as_ref(), None],
+            Self::Ior(_) | Self::Unlit => [None; 3],
+        }
+    }
+}
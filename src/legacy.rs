@@ -0,0 +1,220 @@
+// format v1 (magic "RNM"): every material / texture reference was a `u8`.
+// these types mirror that layout so old files can still be read and migrated.
+
+use crate::{Error, Image, Light, LightUnit, Material, Mesh, Mre, Scene, TextureRef, Uniform};
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) struct MeshV1 {
+    positions: Vec<[f32; 3]>,
+    uvs: Option<Vec<[f32; 2]>>,
+    normals: Option<Vec<[f32; 3]>>,
+    colors: Option<Vec<[f32; 3]>>,
+    tangents: Option<Vec<[f32; 4]>>,
+    indices: Option<Vec<[u16; 3]>>,
+    material: Option<u8>,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) enum UniformV1 {
+    Color([f32; 3]),
+    Texture(u8),
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) enum MreV1 {
+    Texture(u8),
+    Value {
+        metallic: f32,
+        roughness: f32,
+        emission: f32,
+    },
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) struct MaterialV1 {
+    albedo: UniformV1,
+    metallic_roughness_emission: MreV1,
+    normal_texture: Option<u8>,
+    specular: f32,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) enum LightV1 {
+    Point {
+        position: [f32; 3],
+        color: [f32; 3],
+        power: f32,
+        radius: f32,
+    },
+    Sun {
+        direction: [f32; 3],
+        color: [f32; 3],
+        power: f32,
+    },
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        color: [f32; 3],
+        power: f32,
+        radius: f32,
+        blend: f32,
+    },
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) struct ImageV1 {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) struct SceneV1 {
+    meshes: Option<Vec<MeshV1>>,
+    materials: Option<Vec<MaterialV1>>,
+    lights: Option<Vec<LightV1>>,
+    images: Option<Vec<ImageV1>>,
+}
+
+impl MeshV1 {
+    fn migrate(self) -> Mesh {
+        Mesh::from_parts(
+            self.positions,
+            self.uvs,
+            self.normals,
+            self.colors,
+            self.tangents,
+            self.indices,
+            self.material.map(u32::from),
+        )
+    }
+}
+
+impl From<UniformV1> for Uniform {
+    fn from(u: UniformV1) -> Self {
+        match u {
+            UniformV1::Color([r, g, b]) => Self::Color([r, g, b, 1.0]),
+            UniformV1::Texture(t) => Self::new_texture(TextureRef::new(t.into())),
+        }
+    }
+}
+
+impl From<MreV1> for Mre {
+    fn from(m: MreV1) -> Self {
+        match m {
+            MreV1::Texture(t) => Self::Texture {
+                texture: TextureRef::new(t.into()),
+                metallic: 1.0,
+                roughness: 1.0,
+            },
+            MreV1::Value {
+                metallic,
+                roughness,
+                ..
+            } => Self::Value {
+                metallic,
+                roughness,
+            },
+        }
+    }
+}
+
+impl From<MaterialV1> for Material {
+    fn from(m: MaterialV1) -> Self {
+        let emission = match m.metallic_roughness_emission {
+            MreV1::Value { emission, .. } => emission,
+            MreV1::Texture(_) => 0.0,
+        };
+        Self::new(
+            m.albedo.into(),
+            m.metallic_roughness_emission.into(),
+            m.normal_texture.map(|t| TextureRef::new(t.into())),
+            m.specular,
+        )
+        .with_emissive([emission; 3], None, 1.0)
+    }
+}
+
+impl From<LightV1> for Light {
+    fn from(l: LightV1) -> Self {
+        match l {
+            LightV1::Point {
+                position,
+                color,
+                power,
+                radius,
+            } => Self::Point {
+                position,
+                color,
+                power,
+                unit: LightUnit::Watt,
+                radius,
+                range: None,
+                shadows: true,
+            },
+            LightV1::Sun {
+                direction,
+                color,
+                power,
+            } => Self::Sun {
+                direction,
+                color,
+                power,
+                unit: LightUnit::Watt,
+                shadows: true,
+            },
+            LightV1::Spot {
+                position,
+                direction,
+                color,
+                power,
+                radius,
+                blend,
+            } => Self::Spot {
+                position,
+                direction,
+                color,
+                power,
+                unit: LightUnit::Watt,
+                radius,
+                // v1 had no cone size, use half of blender's default 45 degree spot size
+                angle: std::f32::consts::FRAC_PI_8,
+                blend,
+                range: None,
+                shadows: true,
+            },
+        }
+    }
+}
+
+impl From<ImageV1> for Image {
+    fn from(i: ImageV1) -> Self {
+        Self::new(i.width, i.height, i.data)
+    }
+}
+
+impl SceneV1 {
+    pub(crate) fn migrate(self) -> Result<Scene, Error> {
+        fn conv<A, B: From<A>>(v: Option<Vec<A>>) -> Option<Vec<B>> {
+            v.map(|v| v.into_iter().map(B::from).collect())
+        }
+        let meshes = self
+            .meshes
+            .map(|m| m.into_iter().map(MeshV1::migrate).collect());
+        let mut scene = Scene::from_parts(
+            meshes,
+            conv(self.materials),
+            conv(self.lights),
+            conv(self.images),
+            None,
+            None,
+            None,
+        );
+        scene.tag_color_spaces();
+        let errors = scene.validate_references();
+        if !errors.is_empty() {
+            return Err(Error::Invalid(errors));
+        }
+        Ok(scene)
+    }
+}
@@ -1,16 +1,68 @@
+mod alpha_mode;
+mod bcn;
+mod builder;
+mod camera;
+mod color_space;
+mod compression;
+mod container;
+mod environment;
+mod error;
+#[cfg(feature = "safe")]
 mod file;
+mod handle;
+mod ibl;
 mod image;
+mod image_format;
+mod image_kind;
+mod layer;
+#[cfg(feature = "safe")]
+mod legacy;
 mod light;
+mod light_unit;
+mod lightmap;
+#[cfg(feature = "compression")]
+mod lz4hc;
 mod material;
 mod mesh;
+mod mip;
 mod mre;
+mod probe_volume;
+mod rgbe;
+mod sampler;
+mod save_options;
 mod scene;
+mod section;
+mod texture_ref;
 mod uniform;
+mod view;
 
-pub use image::Image;
-pub use light::Light;
-pub use material::Material;
-pub use mesh::Mesh;
+pub use alpha_mode::AlphaMode;
+pub use builder::{MaterialBuilder, MeshBuilder, SceneBuilder};
+pub use camera::{ArchivedCamera, Camera, Projection};
+pub use color_space::ColorSpace;
+pub use compression::Compression;
+pub use environment::{ArchivedEnvironment, Environment};
+pub use error::{Error, ValidationError};
+pub use handle::{ImageHandle, MaterialHandle};
+pub use image::{ArchivedImage, Image};
+pub use image_format::ImageFormat;
+pub use image_kind::ImageKind;
+pub use layer::MaterialLayer;
+pub use light::{ArchivedLight, Light};
+pub use light_unit::{
+    candela_to_lumens, lumens_to_candela, lumens_to_watts, watts_to_lumens, LightUnit,
+    LUMENS_PER_WATT,
+};
+pub use lightmap::Lightmap;
+pub use material::{ArchivedMaterial, Material};
+pub use mesh::{ArchivedMesh, Mesh};
+pub use mip::MipFilter;
 pub use mre::Mre;
+pub use probe_volume::{ArchivedProbeVolume, ProbeVolume};
+pub use sampler::{Filter, Sampler, Wrap};
+pub use save_options::SaveOptions;
 pub use scene::Scene;
+pub use section::Section;
+pub use texture_ref::{TextureRef, TextureTransform};
 pub use uniform::Uniform;
+pub use view::SceneView;
@@ -1,24 +1,76 @@
-#[derive(Debug, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+use crate::{
+    light_unit::{candela_to_lumens, lumens_to_candela, lumens_to_watts, watts_to_lumens},
+    LightUnit, ValidationError,
+};
+
+// `range` is the distance where the light stops, none means infinite.
+// area lights emit along their local -Z axis.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub enum Light {
     Point {
         position: [f32; 3],
         color: [f32; 3],
         power: f32,
+        unit: LightUnit,
         radius: f32,
+        range: Option<f32>,
+        shadows: bool,
     },
     Sun {
         direction: [f32; 3],
         color: [f32; 3],
         power: f32,
+        unit: LightUnit,
+        shadows: bool,
     },
+    // `angle` is the outer cone half angle in radians, `blend` is the
+    // blender style softness: (outer - inner) / outer
     Spot {
         position: [f32; 3],
         direction: [f32; 3],
         color: [f32; 3],
         power: f32,
+        unit: LightUnit,
         radius: f32,
+        angle: f32,
         blend: f32,
+        range: Option<f32>,
+        shadows: bool,
     },
+    Rect {
+        position: [f32; 3],
+        rotation: [f32; 4],
+        color: [f32; 3],
+        power: f32,
+        unit: LightUnit,
+        width: f32,
+        height: f32,
+        shadows: bool,
+    },
+    Disk {
+        position: [f32; 3],
+        direction: [f32; 3],
+        color: [f32; 3],
+        power: f32,
+        unit: LightUnit,
+        radius: f32,
+        shadows: bool,
+    },
+}
+
+// rotates `v` by the quaternion `q` (x, y, z, w)
+fn rotate(q: &[f32; 4], v: [f32; 3]) -> [f32; 3] {
+    let cross = |a: [f32; 3], b: [f32; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let u = [q[0], q[1], q[2]];
+    let t = cross(u, v).map(|x| x * 2.0);
+    let c = cross(u, t);
+    [0, 1, 2].map(|i| v[i] + q[3] * t[i] + c[i])
 }
 
 impl Light {
@@ -27,6 +79,8 @@ impl Light {
             Self::Point { color, .. } => color,
             Self::Sun { color, .. } => color,
             Self::Spot { color, .. } => color,
+            Self::Rect { color, .. } => color,
+            Self::Disk { color, .. } => color,
         }
     }
     pub const fn power(&self) -> f32 {
@@ -34,6 +88,170 @@ impl Light {
             Self::Point { power, .. } => *power,
             Self::Sun { power, .. } => *power,
             Self::Spot { power, .. } => *power,
+            Self::Rect { power, .. } => *power,
+            Self::Disk { power, .. } => *power,
+        }
+    }
+    pub const fn unit(&self) -> LightUnit {
+        match self {
+            Self::Point { unit, .. } => *unit,
+            Self::Sun { unit, .. } => *unit,
+            Self::Spot { unit, .. } => *unit,
+            Self::Rect { unit, .. } => *unit,
+            Self::Disk { unit, .. } => *unit,
+        }
+    }
+    // suns only convert between W/m² and lux, the others between W, lm and cd
+    pub fn power_in(&self, unit: LightUnit) -> Option<f32> {
+        let (power, from) = (self.power(), self.unit());
+        if from == unit {
+            return Some(power);
+        }
+        if let Self::Sun { .. } = self {
+            return match (from, unit) {
+                (LightUnit::Watt, LightUnit::Lux) => Some(watts_to_lumens(power)),
+                (LightUnit::Lux, LightUnit::Watt) => Some(lumens_to_watts(power)),
+                _ => None,
+            };
+        }
+        let solid_angle = match self {
+            Self::Rect { .. } | Self::Disk { .. } => std::f32::consts::PI,
+            _ => 4.0 * std::f32::consts::PI,
+        };
+        let lumens = match from {
+            LightUnit::Watt => watts_to_lumens(power),
+            LightUnit::Lumen => power,
+            LightUnit::Candela => candela_to_lumens(power, solid_angle),
+            LightUnit::Lux => return None,
+        };
+        match unit {
+            LightUnit::Watt => Some(lumens_to_watts(lumens)),
+            LightUnit::Lumen => Some(lumens),
+            LightUnit::Candela => Some(lumens_to_candela(lumens, solid_angle)),
+            LightUnit::Lux => None,
+        }
+    }
+    // rewrites the power in `unit`, false if there is no such conversion
+    pub fn convert(&mut self, unit: LightUnit) -> bool {
+        let Some(value) = self.power_in(unit) else {
+            return false;
+        };
+        let (Self::Point { power, unit: u, .. }
+        | Self::Sun { power, unit: u, .. }
+        | Self::Spot { power, unit: u, .. }
+        | Self::Rect { power, unit: u, .. }
+        | Self::Disk { power, unit: u, .. }) = self;
+        *power = value;
+        *u = unit;
+        true
+    }
+    pub const fn position(&self) -> Option<&[f32; 3]> {
+        match self {
+            Self::Point { position, .. } => Some(position),
+            Self::Sun { .. } => None,
+            Self::Spot { position, .. } => Some(position),
+            Self::Rect { position, .. } => Some(position),
+            Self::Disk { position, .. } => Some(position),
+        }
+    }
+    pub fn direction(&self) -> Option<[f32; 3]> {
+        match self {
+            Self::Point { .. } => None,
+            Self::Sun { direction, .. } => Some(*direction),
+            Self::Spot { direction, .. } => Some(*direction),
+            Self::Rect { rotation, .. } => Some(rotate(rotation, [0.0, 0.0, -1.0])),
+            Self::Disk { direction, .. } => Some(*direction),
+        }
+    }
+    pub const fn range(&self) -> Option<f32> {
+        match self {
+            Self::Point { range, .. } => *range,
+            Self::Spot { range, .. } => *range,
+            Self::Sun { .. } | Self::Rect { .. } | Self::Disk { .. } => None,
+        }
+    }
+    pub const fn shadows(&self) -> bool {
+        match self {
+            Self::Point { shadows, .. } => *shadows,
+            Self::Sun { shadows, .. } => *shadows,
+            Self::Spot { shadows, .. } => *shadows,
+            Self::Rect { shadows, .. } => *shadows,
+            Self::Disk { shadows, .. } => *shadows,
+        }
+    }
+    // (inner, outer) half angles in radians, like glTF
+    pub fn cone_angles(&self) -> Option<(f32, f32)> {
+        match self {
+            Self::Spot { angle, blend, .. } => Some(Self::blend_to_cone(*angle, *blend)),
+            _ => None,
+        }
+    }
+    pub fn cone_to_blend(inner: f32, outer: f32) -> f32 {
+        if outer > 0.0 {
+            ((outer - inner) / outer).clamp(0.0, 1.0)
+        } else {
+            0.0
         }
     }
+    pub fn blend_to_cone(outer: f32, blend: f32) -> (f32, f32) {
+        (outer - outer * blend, outer)
+    }
+    pub(crate) fn validate(&self, light: usize, errors: &mut Vec<ValidationError>) {
+        let len = match self {
+            Self::Point { .. } => 1.0,
+            Self::Rect { rotation, .. } => rotation.iter().map(|x| x * x).sum::<f32>(),
+            Self::Sun { direction, .. }
+            | Self::Spot { direction, .. }
+            | Self::Disk { direction, .. } => direction.iter().map(|x| x * x).sum::<f32>(),
+        };
+        if len == 0.0 || !len.is_finite() {
+            errors.push(ValidationError::LightDirection { light });
+        }
+        if self.power() < 0.0 || self.power().is_nan() {
+            errors.push(ValidationError::LightPower { light });
+        }
+        if self.color().iter().any(|c| *c < 0.0 || c.is_nan()) {
+            errors.push(ValidationError::LightColor { light });
+        }
+        if let Self::Spot { blend, angle, .. } = self {
+            if !(0.0..=1.0).contains(blend) {
+                errors.push(ValidationError::LightBlend { light });
+            }
+            if !(*angle > 0.0 && *angle <= std::f32::consts::FRAC_PI_2) {
+                errors.push(ValidationError::LightAngle { light });
+            }
+        }
+        if let Some(range) = self.range() {
+            if range <= 0.0 || range.is_nan() {
+                errors.push(ValidationError::LightRange { light });
+            }
+        }
+        // point and spot radii can be 0 for a hard light, area sizes can't
+        let size_valid = match self {
+            Self::Rect { width, height, .. } => *width > 0.0 && *height > 0.0,
+            Self::Disk { radius, .. } => *radius > 0.0,
+            Self::Point { radius, .. } | Self::Spot { radius, .. } => *radius >= 0.0,
+            Self::Sun { .. } => true,
+        };
+        if !size_valid {
+            errors.push(ValidationError::LightSize { light });
+        }
+        let unit = self.unit();
+        let valid = match self {
+            Self::Sun { .. } => matches!(unit, LightUnit::Watt | LightUnit::Lux),
+            _ => unit != LightUnit::Lux,
+        };
+        if !valid {
+            errors.push(ValidationError::LightUnitMismatch { light, unit });
+        }
+    }
+}
+
+#[cfg(feature = "safe")]
+impl ArchivedLight {
+    // lights are plain data, checking a native copy keeps both paths identical
+    pub(crate) fn validate(&self, light: usize, errors: &mut Vec<ValidationError>) {
+        let Ok(native) = rkyv::deserialize::<Light, rkyv::rancor::Panic>(self);
+        native.validate(light, errors);
+    }
 }
@@ -0,0 +1,32 @@
+// luminous efficacy used by blender and the glTF exporters
+pub const LUMENS_PER_WATT: f32 = 683.0;
+
+// `Watt` is radiant power for point, spot and area lights and irradiance
+// (W/m²) for suns, `Lux` only makes sense for suns
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum LightUnit {
+    #[default]
+    Watt,
+    Lumen,
+    Candela,
+    Lux,
+}
+
+pub const fn watts_to_lumens(watts: f32) -> f32 {
+    watts * LUMENS_PER_WATT
+}
+
+pub const fn lumens_to_watts(lumens: f32) -> f32 {
+    lumens / LUMENS_PER_WATT
+}
+
+// `solid_angle` is 4π for point and spot lights, π for area lights
+pub const fn lumens_to_candela(lumens: f32, solid_angle: f32) -> f32 {
+    lumens / solid_angle
+}
+
+pub const fn candela_to_lumens(candela: f32, solid_angle: f32) -> f32 {
+    candela * solid_angle
+}
@@ -0,0 +1,73 @@
+use crate::ImageHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct Lightmap {
+    image: u32,
+    uv_set: u32,
+    // lightmap uv = mesh uv * scale + offset, lets many meshes share an atlas
+    scale: [f32; 2],
+    offset: [f32; 2],
+    // set when made from an `ImageHandle`, never stored
+    #[rkyv(with = rkyv::with::Skip)]
+    builder: Option<u32>,
+}
+
+impl ArchivedLightmap {
+    pub const fn image(&self) -> u32 {
+        self.image.to_native()
+    }
+    pub const fn uv_set(&self) -> u32 {
+        self.uv_set.to_native()
+    }
+}
+
+impl Lightmap {
+    pub const fn new(image: u32) -> Self {
+        Self {
+            image,
+            uv_set: 0,
+            scale: [1.0; 2],
+            offset: [0.0; 2],
+            builder: None,
+        }
+    }
+    pub const fn with_uv_set(mut self, uv_set: u32) -> Self {
+        self.uv_set = uv_set;
+        self
+    }
+    pub const fn with_transform(mut self, scale: [f32; 2], offset: [f32; 2]) -> Self {
+        self.scale = scale;
+        self.offset = offset;
+        self
+    }
+    pub const fn image(&self) -> u32 {
+        self.image
+    }
+    pub const fn uv_set(&self) -> u32 {
+        self.uv_set
+    }
+    pub(crate) const fn builder(&self) -> Option<u32> {
+        self.builder
+    }
+    pub const fn scale(&self) -> &[f32; 2] {
+        &self.scale
+    }
+    pub const fn offset(&self) -> &[f32; 2] {
+        &self.offset
+    }
+    pub fn apply(&self, [u, v]: [f32; 2]) -> [f32; 2] {
+        [
+            u * self.scale[0] + self.offset[0],
+            v * self.scale[1] + self.offset[1],
+        ]
+    }
+}
+
+impl From<ImageHandle> for Lightmap {
+    fn from(image: ImageHandle) -> Self {
+        Self {
+            builder: Some(image.builder()),
+            ..Self::new(image.index())
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// lz4 block compressor that follows hash chains instead of keeping one
+// candidate per hash, output is read by the regular lz4 decoder
+
+const MIN_MATCH: usize = 4;
+// the block format wants the last 5 bytes as literals and no match starting
+// in the last 12
+const LAST_LITERALS: usize = 5;
+const MF_LIMIT: usize = 12;
+const WINDOW: usize = u16::MAX as usize;
+const HASH_LOG: u32 = 15;
+
+struct Chains {
+    // last position + 1 for each hash, 0 when empty
+    head: Vec<u32>,
+    // distance to the previous position with the same hash, 0 ends the chain
+    chain: Vec<u16>,
+    next: usize,
+}
+
+fn hash(input: &[u8], pos: usize) -> usize {
+    let bytes = u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]]);
+    (bytes.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+}
+
+impl Chains {
+    fn new() -> Self {
+        Self {
+            head: vec![0; 1 << HASH_LOG],
+            chain: vec![0; WINDOW + 1],
+            next: 0,
+        }
+    }
+    fn insert_up_to(&mut self, input: &[u8], pos: usize) {
+        while self.next < pos {
+            let h = hash(input, self.next);
+            let distance = match self.head[h] as usize {
+                0 => 0,
+                prev => self.next + 1 - prev,
+            };
+            self.chain[self.next & WINDOW] = if distance > WINDOW {
+                0
+            } else {
+                distance as u16
+            };
+            self.head[h] = self.next as u32 + 1;
+            self.next += 1;
+        }
+    }
+    // longest earlier match for `pos` as (length, offset)
+    fn find(&mut self, input: &[u8], pos: usize, end: usize, attempts: usize) -> (usize, usize) {
+        self.insert_up_to(input, pos);
+        let (mut best, mut offset) = (0, 0);
+        let mut candidate = match self.head[hash(input, pos)] as usize {
+            0 => return (0, 0),
+            c => c - 1,
+        };
+        for _ in 0..attempts {
+            if pos - candidate > WINDOW {
+                break;
+            }
+            if input.get(candidate + best) == input.get(pos + best) {
+                let len = input[candidate..end]
+                    .iter()
+                    .zip(&input[pos..end])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best {
+                    (best, offset) = (len, pos - candidate);
+                    if pos + len == end {
+                        break;
+                    }
+                }
+            }
+            match self.chain[candidate & WINDOW] as usize {
+                0 => break,
+                d => candidate -= d,
+            }
+        }
+        (best, offset)
+    }
+}
+
+fn write_len(out: &mut Vec<u8>, mut len: usize) {
+    while len >= 255 {
+        out.push(255);
+        len -= 255;
+    }
+    out.push(len as u8);
+}
+
+fn write_sequence(out: &mut Vec<u8>, literals: &[u8], m: Option<(usize, usize)>) {
+    let extra = m.map_or(0, |(len, _)| len - MIN_MATCH);
+    out.push(((literals.len().min(15) << 4) | extra.min(15)) as u8);
+    if literals.len() >= 15 {
+        write_len(out, literals.len() - 15);
+    }
+    out.extend_from_slice(literals);
+    if let Some((_, offset)) = m {
+        out.extend_from_slice(&(offset as u16).to_le_bytes());
+        if extra >= 15 {
+            write_len(out, extra - 15);
+        }
+    }
+}
+
+// `level` 1..=12 doubles the chain steps per level
+pub(crate) fn compress(input: &[u8], level: u8) -> Vec<u8> {
+    let attempts = 1 << (level.clamp(1, 12) - 1);
+    let mut out = Vec::with_capacity(input.len() / 2 + 16);
+    let mut anchor = 0;
+    if input.len() > MF_LIMIT {
+        let mut chains = Chains::new();
+        let end = input.len() - LAST_LITERALS;
+        let last = input.len() - MF_LIMIT;
+        let mut pos = 0;
+        while pos <= last {
+            let (mut len, mut offset) = chains.find(input, pos, end, attempts);
+            if len < MIN_MATCH {
+                pos += 1;
+                continue;
+            }
+            // lazy matching: a longer match one byte later is worth a literal
+            while pos < last {
+                let (next, next_offset) = chains.find(input, pos + 1, end, attempts);
+                if next <= len {
+                    break;
+                }
+                (pos, len, offset) = (pos + 1, next, next_offset);
+            }
+            write_sequence(&mut out, &input[anchor..pos], Some((len, offset)));
+            pos += len;
+            anchor = pos;
+        }
+    }
+    write_sequence(&mut out, &input[anchor..], None);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, MF_LIMIT, WINDOW};
+
+    fn round_trip(input: &[u8]) {
+        for level in [1, 9, 12] {
+            let packed = compress(input, level);
+            let unpacked = lz4_flex::block::decompress(&packed, input.len()).unwrap();
+            assert!(unpacked == input, "level {level}, {} bytes", input.len());
+        }
+    }
+
+    // xorshift, nothing for the match finder to find
+    fn random(len: usize, mut state: u32) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small() {
+        for len in 0..=MF_LIMIT * 2 {
+            round_trip(&random(len, len as u32 + 1));
+            round_trip(&vec![7; len]);
+        }
+    }
+
+    #[test]
+    fn repetitive() {
+        round_trip(&vec![0; 100_000]);
+        round_trip(&b"abcabcabd".repeat(20_000));
+        // long literal runs and long matches both need extra length bytes
+        let block = random(1000, 3);
+        round_trip(&[block.as_slice(), &[1; 600], &block, &block].concat());
+    }
+
+    #[test]
+    fn random_data() {
+        for (len, seed) in [(100, 1), (4096, 2), (70_000, 3), (200_000, 4)] {
+            round_trip(&random(len, seed));
+        }
+    }
+
+    // repeats just inside and just past the window
+    #[test]
+    fn distant_matches() {
+        let block = random(256, 5);
+        for gap in [WINDOW - 256, WINDOW - 255, WINDOW + 1] {
+            let filler = random(gap, gap as u32);
+            round_trip(&[block.as_slice(), &filler, &block].concat());
+        }
+    }
+}
@@ -1,33 +1,68 @@
-use crate::{Mre, Uniform};
+use crate::{
+    texture_ref::ArchivedTextureRef, AlphaMode, ColorSpace, MaterialLayer, Mre, TextureRef,
+    Uniform, ValidationError,
+};
 
 #[derive(Debug, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub struct Material {
     albedo: Uniform,
-    metallic_roughness_emission: Mre,
-    normal_texture: Option<u8>,
+    metallic_roughness: Mre,
+    normal_texture: Option<TextureRef>,
+    normal_scale: f32,
+    occlusion_texture: Option<TextureRef>,
+    occlusion_strength: f32,
+    emissive: [f32; 3],
+    emissive_texture: Option<TextureRef>,
+    emissive_strength: f32,
+    alpha_mode: AlphaMode,
+    double_sided: bool,
     specular: f32,
+    layers: Vec<MaterialLayer>,
+}
+
+const fn archived_texture(
+    texture: &rkyv::option::ArchivedOption<ArchivedTextureRef>,
+) -> Option<&ArchivedTextureRef> {
+    match texture {
+        rkyv::option::ArchivedOption::None => None,
+        rkyv::option::ArchivedOption::Some(x) => Some(x),
+    }
 }
 
 impl ArchivedMaterial {
+    fn textures(&self) -> impl Iterator<Item = &ArchivedTextureRef> + '_ {
+        let textures = [
+            self.albedo.texture(),
+            self.metallic_roughness.texture(),
+            archived_texture(&self.normal_texture),
+            archived_texture(&self.occlusion_texture),
+            archived_texture(&self.emissive_texture),
+        ];
+        let layers = self.layers.iter().flat_map(|l| l.textures());
+        textures.into_iter().chain(layers).flatten()
+    }
+    pub(crate) fn images(&self) -> impl Iterator<Item = u32> + '_ {
+        self.textures().map(ArchivedTextureRef::image)
+    }
     #[cfg(feature = "safe")]
-    const fn normal_texture(&self) -> Option<u8> {
-        match self.normal_texture {
-            rkyv::option::ArchivedOption::None => None,
-            rkyv::option::ArchivedOption::Some(x) => Some(x),
-        }
+    pub(crate) fn uv_sets(&self) -> impl Iterator<Item = u32> + '_ {
+        self.textures().map(ArchivedTextureRef::uv_set)
     }
     #[cfg(feature = "safe")]
-    pub(crate) const fn max_texture_idx(&self) -> Option<u8> {
-        let t = self.albedo.texture();
-        let m = self.metallic_roughness_emission.texture();
-        let n = self.normal_texture();
-        if t.is_none() && m.is_none() && n.is_none() {
-            None
-        } else {
-            let t = const_unwrap_or(t, 0);
-            let m = const_unwrap_or(m, 0);
-            let n = const_unwrap_or(n, 0);
-            Some(const_max(const_max(t, m), n))
+    pub(crate) fn validate(
+        &self,
+        material: usize,
+        images: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for image in self.images() {
+            if image as usize >= images {
+                errors.push(ValidationError::ImageOutOfRange {
+                    material,
+                    image,
+                    images,
+                });
+            }
         }
     }
 }
@@ -35,69 +70,165 @@ impl ArchivedMaterial {
 impl Material {
     pub const fn new(
         albedo: Uniform,
-        metallic_roughness_emission: Mre,
-        normal_texture: Option<u8>,
+        metallic_roughness: Mre,
+        normal_texture: Option<TextureRef>,
         specular: f32,
     ) -> Self {
         Self {
             albedo,
-            metallic_roughness_emission,
+            metallic_roughness,
             normal_texture,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: [0.0; 3],
+            emissive_texture: None,
+            emissive_strength: 1.0,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
             specular,
+            layers: Vec::new(),
         }
     }
+    pub const fn with_normal_scale(mut self, scale: f32) -> Self {
+        self.normal_scale = scale;
+        self
+    }
+    pub const fn with_occlusion(mut self, texture: Option<TextureRef>, strength: f32) -> Self {
+        self.occlusion_texture = texture;
+        self.occlusion_strength = strength;
+        self
+    }
+    pub const fn with_emissive(
+        mut self,
+        color: [f32; 3],
+        texture: Option<TextureRef>,
+        strength: f32,
+    ) -> Self {
+        self.emissive = color;
+        self.emissive_texture = texture;
+        self.emissive_strength = strength;
+        self
+    }
+    pub const fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+    pub const fn with_double_sided(mut self, double_sided: bool) -> Self {
+        self.double_sided = double_sided;
+        self
+    }
+    pub fn with_layer(mut self, layer: MaterialLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
     pub const fn albedo(&self) -> &Uniform {
         &self.albedo
     }
-    pub const fn metallic_roughness_emission(&self) -> &Mre {
-        &self.metallic_roughness_emission
+    pub const fn metallic_roughness(&self) -> &Mre {
+        &self.metallic_roughness
     }
-    pub const fn normal_texture(&self) -> Option<u8> {
-        self.normal_texture
+    pub const fn normal_texture(&self) -> Option<&TextureRef> {
+        self.normal_texture.as_ref()
+    }
+    pub const fn normal_scale(&self) -> f32 {
+        self.normal_scale
+    }
+    pub const fn occlusion_texture(&self) -> Option<&TextureRef> {
+        self.occlusion_texture.as_ref()
+    }
+    pub const fn occlusion_strength(&self) -> f32 {
+        self.occlusion_strength
+    }
+    pub const fn emissive(&self) -> &[f32; 3] {
+        &self.emissive
+    }
+    pub const fn emissive_texture(&self) -> Option<&TextureRef> {
+        self.emissive_texture.as_ref()
+    }
+    pub const fn emissive_strength(&self) -> f32 {
+        self.emissive_strength
+    }
+    pub const fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+    pub const fn double_sided(&self) -> bool {
+        self.double_sided
     }
     pub const fn specular(&self) -> f32 {
         self.specular
     }
-    #[cfg(feature = "safe")]
-    pub(crate) const fn max_texture_idx(&self) -> Option<u8> {
-        let t = self.albedo.texture();
-        let m = self.metallic_roughness_emission.texture();
-        let n = self.normal_texture;
-        if t.is_none() && m.is_none() && n.is_none() {
-            None
-        } else {
-            let t = const_unwrap_or(t, 0);
-            let m = const_unwrap_or(m, 0);
-            let n = const_unwrap_or(n, 0);
-            Some(const_max(const_max(t, m), n))
-        }
+    pub fn layers(&self) -> &[MaterialLayer] {
+        &self.layers
     }
-}
-
-#[cfg(feature = "safe")]
-const fn const_max(a: u8, b: u8) -> u8 {
-    if a > b {
-        a
-    } else {
-        b
+    pub fn is_unlit(&self) -> bool {
+        self.layers.contains(&MaterialLayer::Unlit)
     }
-}
-
-#[cfg(feature = "safe")]
-const fn const_unwrap_or(i: Option<u8>, v: u8) -> u8 {
-    match i {
-        Some(x) => x,
-        None => v,
+    pub(crate) fn normal_images(&self) -> impl Iterator<Item = u32> + '_ {
+        let clearcoat = self.layers.iter().filter_map(|l| match l {
+            MaterialLayer::Clearcoat { normal_texture, .. } => normal_texture.as_ref(),
+            _ => None,
+        });
+        self.normal_texture
+            .iter()
+            .chain(clearcoat)
+            .map(TextureRef::image)
+    }
+    // color textures are sRGB, data textures (normal, mre, ...) are linear
+    pub(crate) fn image_color_spaces(&self) -> impl Iterator<Item = (u32, ColorSpace)> + '_ {
+        let linear = [
+            self.metallic_roughness.texture(),
+            self.normal_texture.as_ref(),
+            self.occlusion_texture.as_ref(),
+        ]
+        .into_iter()
+        .chain(self.layers.iter().flat_map(MaterialLayer::textures))
+        .flatten()
+        .map(|t| (t.image(), ColorSpace::Linear));
+        let srgb = [self.albedo.texture(), self.emissive_texture.as_ref()]
+            .into_iter()
+            .chain(self.layers.iter().map(MaterialLayer::color_texture))
+            .flatten()
+            .map(|t| (t.image(), ColorSpace::Srgb));
+        linear.chain(srgb)
+    }
+    pub(crate) fn textures(&self) -> impl Iterator<Item = &TextureRef> + '_ {
+        let textures = [
+            self.albedo.texture(),
+            self.metallic_roughness.texture(),
+            self.normal_texture.as_ref(),
+            self.occlusion_texture.as_ref(),
+            self.emissive_texture.as_ref(),
+        ];
+        let layers = self.layers.iter().flat_map(MaterialLayer::textures);
+        textures.into_iter().chain(layers).flatten()
+    }
+    pub(crate) fn images(&self) -> impl Iterator<Item = u32> + '_ {
+        self.textures().map(TextureRef::image)
+    }
+    pub(crate) fn uv_sets(&self) -> impl Iterator<Item = u32> + '_ {
+        self.textures().map(TextureRef::uv_set)
+    }
+    pub(crate) fn validate(
+        &self,
+        material: usize,
+        images: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for image in self.images() {
+            if image as usize >= images {
+                errors.push(ValidationError::ImageOutOfRange {
+                    material,
+                    image,
+                    images,
+                });
+            }
+        }
     }
 }
 
 impl Default for Material {
     fn default() -> Self {
-        Self {
-            albedo: Uniform::default(),
-            specular: 0.5,
-            normal_texture: None,
-            metallic_roughness_emission: Mre::default(),
-        }
+        Self::new(Uniform::default(), Mre::default(), None, 0.5)
     }
 }
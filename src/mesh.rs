@@ -1,3 +1,5 @@
+use crate::{Lightmap, Material, ValidationError};
+
 #[derive(Debug, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub struct Mesh {
     positions: Vec<[f32; 3]>,
@@ -6,45 +8,136 @@ pub struct Mesh {
     colors: Option<Vec<[f32; 3]>>,
     tangents: Option<Vec<[f32; 4]>>,
     indices: Option<Vec<[u16; 3]>>,
-    material: Option<u8>,
+    material: Option<u32>,
+    lightmap: Option<Lightmap>,
+    // uv set 1, usually a lightmap unwrap
+    uvs1: Option<Vec<[f32; 2]>>,
+}
+
+// which uv sets a mesh has, indexed by set
+fn has_uv_set(uv_sets: [bool; 2], uv_set: u32) -> bool {
+    uv_sets.get(uv_set as usize).copied().unwrap_or(false)
+}
+
+// the textures of a mesh's material can only sample uv sets the mesh has.
+// meshes without any uvs are left alone, they were always allowed to use
+// textured materials
+fn texture_uv_set_errors(
+    mesh: usize,
+    material: u32,
+    uv_sets: [bool; 2],
+    mut used: impl Iterator<Item = u32>,
+    errors: &mut Vec<ValidationError>,
+) {
+    if uv_sets == [false; 2] {
+        return;
+    }
+    if let Some(uv_set) = used.find(|uv_set| !has_uv_set(uv_sets, *uv_set)) {
+        errors.push(ValidationError::TextureUvSet {
+            mesh,
+            material,
+            uv_set,
+        });
+    }
+}
+
+fn lightmap_errors(
+    mesh: usize,
+    (image, uv_set): (u32, u32),
+    uv_sets: [bool; 2],
+    images: usize,
+    errors: &mut Vec<ValidationError>,
+) {
+    if image as usize >= images {
+        errors.push(ValidationError::LightmapImageOutOfRange {
+            mesh,
+            image,
+            images,
+        });
+    }
+    if !has_uv_set(uv_sets, uv_set) {
+        errors.push(ValidationError::LightmapUvSet { mesh, uv_set });
+    }
 }
 
 #[cfg(feature = "safe")]
 impl ArchivedMesh {
-    pub(crate) fn isnt_right(&self) -> bool {
+    fn uv_sets(&self) -> [bool; 2] {
+        [self.uvs.is_some(), self.uvs1.is_some()]
+    }
+    pub(crate) fn validate_uv_sets(
+        &self,
+        mesh: usize,
+        materials: &[crate::ArchivedMaterial],
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let rkyv::option::ArchivedOption::Some(material) = self.material {
+            if let Some(m) = materials.get(material.to_native() as usize) {
+                texture_uv_set_errors(
+                    mesh,
+                    material.to_native(),
+                    self.uv_sets(),
+                    m.uv_sets(),
+                    errors,
+                );
+            }
+        }
+    }
+    pub(crate) fn validate(
+        &self,
+        mesh: usize,
+        materials: usize,
+        images: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
         let l = self.positions.len();
-        l != self.uvs.as_ref().map(|x| x.len()).unwrap_or(l)
+        if l != self.uvs.as_ref().map(|x| x.len()).unwrap_or(l)
             || l != self.normals.as_ref().map(|x| x.len()).unwrap_or(l)
             || l != self.colors.as_ref().map(|x| x.len()).unwrap_or(l)
             || l != self.tangents.as_ref().map(|x| x.len()).unwrap_or(l)
-            || self
-                .tangents
-                .as_ref()
-                .map(|vec| {
-                    vec.into_iter()
-                        .any(|[_, _, _, w]| f32::from(w).abs() != 1.0)
-                })
-                .unwrap_or(false)
-    }
-    pub(crate) const fn material(&self) -> Option<u8> {
-        match self.material {
-            rkyv::option::ArchivedOption::None => None,
-            rkyv::option::ArchivedOption::Some(x) => Some(x),
+            || l != self.uvs1.as_ref().map(|x| x.len()).unwrap_or(l)
+        {
+            errors.push(ValidationError::AttributeLenMismatch { mesh });
+        }
+        if self
+            .tangents
+            .as_ref()
+            .is_some_and(|vec| vec.iter().any(|[_, _, _, w]| f32::from(w).abs() != 1.0))
+        {
+            errors.push(ValidationError::TangentSign { mesh });
+        }
+        if let rkyv::option::ArchivedOption::Some(material) = self.material {
+            if material.to_native() as usize >= materials {
+                errors.push(ValidationError::MaterialOutOfRange {
+                    mesh,
+                    material: material.to_native(),
+                    materials,
+                });
+            }
+        }
+        if let Some(lightmap) = self.lightmap.as_ref() {
+            lightmap_errors(
+                mesh,
+                (lightmap.image(), lightmap.uv_set()),
+                self.uv_sets(),
+                images,
+                errors,
+            );
         }
     }
 }
 
 impl Mesh {
-    pub fn new(
+    pub(crate) const fn from_parts(
         positions: Vec<[f32; 3]>,
         uvs: Option<Vec<[f32; 2]>>,
         normals: Option<Vec<[f32; 3]>>,
         colors: Option<Vec<[f32; 3]>>,
         tangents: Option<Vec<[f32; 4]>>,
         indices: Option<Vec<[u16; 3]>>,
-        material: Option<u8>,
-    ) -> Option<Self> {
-        let ret = Self {
+        material: Option<u32>,
+    ) -> Self {
+        Self {
             positions,
             uvs,
             normals,
@@ -52,12 +145,29 @@ impl Mesh {
             tangents,
             indices,
             material,
-        };
+            lightmap: None,
+            uvs1: None,
+        }
+    }
+    pub fn new(
+        positions: Vec<[f32; 3]>,
+        uvs: Option<Vec<[f32; 2]>>,
+        normals: Option<Vec<[f32; 3]>>,
+        colors: Option<Vec<[f32; 3]>>,
+        tangents: Option<Vec<[f32; 4]>>,
+        indices: Option<Vec<[u16; 3]>>,
+        material: Option<u32>,
+    ) -> Option<Self> {
+        let ret = Self::from_parts(positions, uvs, normals, colors, tangents, indices, material);
         #[cfg(feature = "safe")]
-        if ret.isnt_right() {
-            None
-        } else {
-            Some(ret)
+        {
+            let mut errors = Vec::new();
+            ret.validate(0, usize::MAX, usize::MAX, &mut errors);
+            if errors.is_empty() {
+                Some(ret)
+            } else {
+                None
+            }
         }
         #[cfg(not(feature = "safe"))]
         Some(ret)
@@ -68,6 +178,32 @@ impl Mesh {
     pub fn uvs(&self) -> Option<&[[f32; 2]]> {
         self.uvs.as_deref()
     }
+    pub fn uvs1(&self) -> Option<&[[f32; 2]]> {
+        self.uvs1.as_deref()
+    }
+    pub fn uv_set(&self, uv_set: u32) -> Option<&[[f32; 2]]> {
+        match uv_set {
+            0 => self.uvs(),
+            1 => self.uvs1(),
+            _ => None,
+        }
+    }
+    const fn uv_sets(&self) -> [bool; 2] {
+        [self.uvs.is_some(), self.uvs1.is_some()]
+    }
+    // checked apart from `validate` since it needs the materials themselves
+    pub(crate) fn validate_uv_sets(
+        &self,
+        mesh: usize,
+        materials: &[Material],
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(material) = self.material {
+            if let Some(m) = materials.get(material as usize) {
+                texture_uv_set_errors(mesh, material, self.uv_sets(), m.uv_sets(), errors);
+            }
+        }
+    }
     pub fn normals(&self) -> Option<&[[f32; 3]]> {
         self.normals.as_deref()
     }
@@ -80,20 +216,60 @@ impl Mesh {
     pub fn indices(&self) -> Option<&[[u16; 3]]> {
         self.indices.as_deref()
     }
-    pub const fn material(&self) -> Option<u8> {
+    pub const fn material(&self) -> Option<u32> {
         self.material
     }
-    #[cfg(feature = "safe")]
-    pub(crate) fn isnt_right(&self) -> bool {
+    pub const fn lightmap(&self) -> Option<&Lightmap> {
+        self.lightmap.as_ref()
+    }
+    pub const fn with_lightmap(mut self, lightmap: Lightmap) -> Self {
+        self.lightmap = Some(lightmap);
+        self
+    }
+    pub fn with_uvs1(mut self, uvs1: Vec<[f32; 2]>) -> Self {
+        self.uvs1 = Some(uvs1);
+        self
+    }
+    pub(crate) fn validate(
+        &self,
+        mesh: usize,
+        materials: usize,
+        images: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
         let l = self.positions.len();
-        l != self.uvs.as_ref().map(|x| x.len()).unwrap_or(l)
+        if l != self.uvs.as_ref().map(|x| x.len()).unwrap_or(l)
             || l != self.normals.as_ref().map(|x| x.len()).unwrap_or(l)
             || l != self.colors.as_ref().map(|x| x.len()).unwrap_or(l)
             || l != self.tangents.as_ref().map(|x| x.len()).unwrap_or(l)
-            || self
-                .tangents
-                .as_ref()
-                .map(|vec| vec.into_iter().any(|[_, _, _, w]| w.abs() != 1.0))
-                .unwrap_or(false)
+            || l != self.uvs1.as_ref().map(|x| x.len()).unwrap_or(l)
+        {
+            errors.push(ValidationError::AttributeLenMismatch { mesh });
+        }
+        if self
+            .tangents
+            .as_ref()
+            .is_some_and(|vec| vec.iter().any(|[_, _, _, w]| w.abs() != 1.0))
+        {
+            errors.push(ValidationError::TangentSign { mesh });
+        }
+        if let Some(material) = self.material {
+            if material as usize >= materials {
+                errors.push(ValidationError::MaterialOutOfRange {
+                    mesh,
+                    material,
+                    materials,
+                });
+            }
+        }
+        if let Some(lightmap) = &self.lightmap {
+            lightmap_errors(
+                mesh,
+                (lightmap.image(), lightmap.uv_set()),
+                self.uv_sets(),
+                images,
+                errors,
+            );
+        }
     }
 }
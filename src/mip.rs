@@ -0,0 +1,202 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MipFilter {
+    #[default]
+    Box,
+    Kaiser,
+}
+
+// half-width of the kaiser window in destination pixels, and its alpha
+const KAISER_WIDTH: f32 = 2.0;
+const KAISER_ALPHA: f32 = 4.0;
+
+pub(crate) struct Surface {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) channels: usize,
+    pub(crate) data: Vec<f32>,
+}
+
+pub(crate) fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl Surface {
+    pub(crate) fn from_u8(
+        width: usize,
+        height: usize,
+        channels: usize,
+        data: &[u8],
+        srgb: bool,
+    ) -> Self {
+        let data = data
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let c = *x as f32 / 255.0;
+                if srgb && i % channels < 3 {
+                    srgb_to_linear(c)
+                } else {
+                    c
+                }
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            channels,
+            data,
+        }
+    }
+    pub(crate) fn to_u8(&self, srgb: bool) -> Vec<u8> {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let c = c.clamp(0.0, 1.0);
+                let c = if srgb && i % self.channels < 3 {
+                    linear_to_srgb(c)
+                } else {
+                    c
+                };
+                (c * 255.0 + 0.5) as u8
+            })
+            .collect()
+    }
+    pub(crate) fn downsample(&self, filter: MipFilter) -> Self {
+        let (data, width) = resample(
+            &self.data,
+            self.width,
+            self.height,
+            self.channels,
+            true,
+            filter,
+        );
+        let (data, height) = resample(&data, width, self.height, self.channels, false, filter);
+        Self {
+            width,
+            height,
+            channels: self.channels,
+            data,
+        }
+    }
+    pub(crate) fn renormalize(&mut self) {
+        for px in self.data.chunks_mut(self.channels) {
+            let n = [px[0] * 2.0 - 1.0, px[1] * 2.0 - 1.0, px[2] * 2.0 - 1.0];
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            if len > 0.0 {
+                for (c, n) in px.iter_mut().zip(n) {
+                    *c = n / len * 0.5 + 0.5;
+                }
+            }
+        }
+    }
+}
+
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-8 {
+        term *= (x / (2.0 * k)) * (x / (2.0 * k));
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        let x = x * std::f32::consts::PI;
+        x.sin() / x
+    }
+}
+
+// `x` is the distance to the destination pixel center, in destination pixels
+fn weight(filter: MipFilter, x: f32) -> f32 {
+    match filter {
+        MipFilter::Box => {
+            if x.abs() < 0.5 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        MipFilter::Kaiser => {
+            if x.abs() >= KAISER_WIDTH {
+                0.0
+            } else {
+                let r = x / KAISER_WIDTH;
+                sinc(x) * bessel_i0(KAISER_ALPHA * (1.0 - r * r).sqrt()) / bessel_i0(KAISER_ALPHA)
+            }
+        }
+    }
+}
+
+fn resample(
+    src: &[f32],
+    width: usize,
+    height: usize,
+    channels: usize,
+    horizontal: bool,
+    filter: MipFilter,
+) -> (Vec<f32>, usize) {
+    let n = if horizontal { width } else { height };
+    let m = (n / 2).max(1);
+    if m == n {
+        return (src.to_vec(), n);
+    }
+    let scale = n as f32 / m as f32;
+    let support = match filter {
+        MipFilter::Box => 0.5,
+        MipFilter::Kaiser => KAISER_WIDTH,
+    } * scale;
+    let taps = (0..m)
+        .map(|x| {
+            let center = (x as f32 + 0.5) * scale;
+            let lo = (center - support - 0.5).floor() as isize;
+            let hi = (center + support + 0.5).ceil() as isize;
+            let mut taps = (lo..=hi)
+                .map(|i| {
+                    let w = weight(filter, (i as f32 + 0.5 - center) / scale);
+                    (i.clamp(0, n as isize - 1) as usize, w)
+                })
+                .filter(|(_, w)| *w != 0.0)
+                .collect::<Vec<_>>();
+            let sum = taps.iter().map(|(_, w)| w).sum::<f32>();
+            for (_, w) in &mut taps {
+                *w /= sum;
+            }
+            taps
+        })
+        .collect::<Vec<_>>();
+    let (w, h) = if horizontal { (m, height) } else { (width, m) };
+    let mut dst = vec![0.0; w * h * channels];
+    for y in 0..h {
+        for x in 0..w {
+            let (d, row) = if horizontal { (x, y) } else { (y, x) };
+            for (i, wt) in &taps[d] {
+                let (sx, sy) = if horizontal { (*i, row) } else { (row, *i) };
+                let s = (sy * width + sx) * channels;
+                let o = (y * w + x) * channels;
+                for c in 0..channels {
+                    dst[o + c] += src[s + c] * wt;
+                }
+            }
+        }
+    }
+    (dst, m)
+}
@@ -1,61 +1,61 @@
+use crate::{texture_ref::ArchivedTextureRef, TextureRef};
+
 #[derive(rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub enum Mre {
-    Texture(u8),
+    Texture {
+        texture: TextureRef,
+        metallic: f32,
+        roughness: f32,
+    },
     Value {
         metallic: f32,
         roughness: f32,
-        emission: f32,
     },
 }
 
 impl std::fmt::Debug for Mre {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Texture(x) => f.write_fmt(format_args!("Texture({x})")),
-            Self::Value {
+            Self::Texture {
+                texture: x,
                 metallic: m,
                 roughness: r,
-                emission: e,
             } => f.write_fmt(format_args!(
-                "(metallic: {m}, roughness: {r}, emission: {e})"
+                "Texture({}) * (metallic: {m}, roughness: {r})",
+                x.image()
             )),
+            Self::Value {
+                metallic: m,
+                roughness: r,
+            } => f.write_fmt(format_args!("(metallic: {m}, roughness: {r})")),
         }
     }
 }
 
-#[cfg(feature = "safe")]
 impl ArchivedMre {
-    pub(crate) const fn texture(&self) -> Option<u8> {
+    pub(crate) const fn texture(&self) -> Option<&ArchivedTextureRef> {
         match self {
-            Self::Texture(t) => Some(*t),
+            Self::Texture { texture, .. } => Some(texture),
             _ => None,
         }
     }
 }
 
 impl Mre {
-    pub const fn texture(&self) -> Option<u8> {
+    pub const fn texture(&self) -> Option<&TextureRef> {
         match self {
-            Self::Texture(t) => Some(*t),
+            Self::Texture { texture, .. } => Some(texture),
             _ => None,
         }
     }
-    pub const fn metallic(&self) -> Option<f32> {
+    pub const fn metallic(&self) -> f32 {
         match self {
-            Self::Value { metallic: x, .. } => Some(*x),
-            _ => None,
+            Self::Texture { metallic, .. } | Self::Value { metallic, .. } => *metallic,
         }
     }
-    pub const fn roughness(&self) -> Option<f32> {
+    pub const fn roughness(&self) -> f32 {
         match self {
-            Self::Value { roughness: x, .. } => Some(*x),
-            _ => None,
-        }
-    }
-    pub const fn emission(&self) -> Option<f32> {
-        match self {
-            Self::Value { emission: x, .. } => Some(*x),
-            _ => None,
+            Self::Texture { roughness, .. } | Self::Value { roughness, .. } => *roughness,
         }
     }
 }
@@ -65,7 +65,6 @@ impl Default for Mre {
         Self::Value {
             metallic: 0.0,
             roughness: 0.5,
-            emission: 0.0,
         }
     }
 }
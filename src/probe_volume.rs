@@ -0,0 +1,160 @@
+use crate::{ibl, ValidationError};
+
+type Sh9 = [[f32; 3]; 9];
+
+// a grid of irradiance probes spanning `min..max`, the outer probes sit on
+// the bounds. `coefficients` holds one L2 SH (RGB) per probe, x fastest then
+// y then z, cosine convolved like `Environment::irradiance`.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct ProbeVolume {
+    min: [f32; 3],
+    max: [f32; 3],
+    resolution: [u32; 3],
+    coefficients: Vec<Sh9>,
+}
+
+fn check(
+    volume: usize,
+    min: [f32; 3],
+    max: [f32; 3],
+    resolution: [u32; 3],
+    len: usize,
+    errors: &mut Vec<ValidationError>,
+) {
+    let expected = resolution
+        .iter()
+        .try_fold(1usize, |n, r| n.checked_mul(*r as usize));
+    if resolution.contains(&0)
+        || expected.is_none()
+        || (0..3).any(|i| !min[i].is_finite() || !max[i].is_finite() || min[i] > max[i])
+    {
+        errors.push(ValidationError::ProbeGrid { volume });
+    }
+    if let Some(expected) = expected.filter(|e| *e != len) {
+        errors.push(ValidationError::ProbeCount {
+            volume,
+            expected,
+            len,
+        });
+    }
+}
+
+impl ArchivedProbeVolume {
+    pub fn min(&self) -> [f32; 3] {
+        self.min.each_ref().map(|x| x.to_native())
+    }
+    pub fn max(&self) -> [f32; 3] {
+        self.max.each_ref().map(|x| x.to_native())
+    }
+    pub fn resolution(&self) -> [u32; 3] {
+        self.resolution.each_ref().map(|x| x.to_native())
+    }
+    pub fn probe_count(&self) -> usize {
+        self.coefficients.len()
+    }
+    pub fn probe(&self, index: usize) -> Option<Sh9> {
+        let sh = self.coefficients.get(index)?;
+        Some(sh.each_ref().map(|c| c.each_ref().map(|x| x.to_native())))
+    }
+    #[cfg(feature = "safe")]
+    pub(crate) fn validate(&self, volume: usize, errors: &mut Vec<ValidationError>) {
+        check(
+            volume,
+            self.min(),
+            self.max(),
+            self.resolution(),
+            self.coefficients.len(),
+            errors,
+        );
+    }
+}
+
+impl ProbeVolume {
+    pub fn new(min: [f32; 3], max: [f32; 3], resolution: [u32; 3], coefficients: Vec<Sh9>) -> Self {
+        Self {
+            min,
+            max,
+            resolution,
+            coefficients,
+        }
+    }
+    pub const fn min(&self) -> &[f32; 3] {
+        &self.min
+    }
+    pub const fn max(&self) -> &[f32; 3] {
+        &self.max
+    }
+    pub const fn resolution(&self) -> &[u32; 3] {
+        &self.resolution
+    }
+    pub fn coefficients(&self) -> &[Sh9] {
+        &self.coefficients
+    }
+    pub fn probe(&self, [x, y, z]: [u32; 3]) -> Option<&Sh9> {
+        let [rx, ry, rz] = self.resolution;
+        if x >= rx || y >= ry || z >= rz {
+            return None;
+        }
+        let i = (z as usize * ry as usize + y as usize) * rx as usize + x as usize;
+        self.coefficients.get(i)
+    }
+    pub fn probe_position(&self, probe: [u32; 3]) -> [f32; 3] {
+        [0, 1, 2].map(|i| {
+            let steps = self.resolution[i].saturating_sub(1).max(1) as f32;
+            self.min[i] + (self.max[i] - self.min[i]) * probe[i] as f32 / steps
+        })
+    }
+    // trilinear blend of the 8 surrounding probes, none outside the bounds
+    pub fn sample(&self, point: [f32; 3]) -> Option<Sh9> {
+        let mut cell = [0; 3];
+        let mut t = [0.0; 3];
+        for i in 0..3 {
+            let (min, max) = (self.min[i], self.max[i]);
+            if !(min..=max).contains(&point[i]) {
+                return None;
+            }
+            let steps = self.resolution[i].saturating_sub(1);
+            let f = if max > min {
+                (point[i] - min) / (max - min) * steps as f32
+            } else {
+                0.0
+            };
+            cell[i] = (f as u32).min(steps.saturating_sub(1));
+            t[i] = (f - cell[i] as f32).clamp(0.0, 1.0);
+        }
+        let mut sh = [[0.0; 3]; 9];
+        for corner in 0..8u32 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight = (0..3)
+                .map(|i| if offset[i] == 1 { t[i] } else { 1.0 - t[i] })
+                .product::<f32>();
+            if weight == 0.0 {
+                continue;
+            }
+            let probe = self.probe(
+                [0, 1, 2].map(|i| (cell[i] + offset[i]).min(self.resolution[i].saturating_sub(1))),
+            )?;
+            for (out, p) in sh.iter_mut().zip(probe) {
+                for c in 0..3 {
+                    out[c] += p[c] * weight;
+                }
+            }
+        }
+        Some(sh)
+    }
+    pub fn irradiance_at(&self, point: [f32; 3], normal: [f32; 3]) -> Option<[f32; 3]> {
+        let sh = self.sample(point)?;
+        let basis = ibl::sh_basis(normal);
+        Some([0, 1, 2].map(|c| sh.iter().zip(basis).map(|(s, b)| s[c] * b).sum()))
+    }
+    pub(crate) fn validate(&self, volume: usize, errors: &mut Vec<ValidationError>) {
+        check(
+            volume,
+            self.min,
+            self.max,
+            self.resolution,
+            self.coefficients.len(),
+            errors,
+        );
+    }
+}
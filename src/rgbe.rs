@@ -0,0 +1,33 @@
+// radiance RGBE: 8 bit mantissas with a shared exponent biased by 128
+
+pub(crate) fn encode(rgb: &[f32]) -> Vec<u8> {
+    rgb.chunks_exact(3)
+        .flat_map(|p| {
+            let [r, g, b] = [p[0], p[1], p[2]].map(|c| c.max(0.0));
+            let max = r.max(g).max(b);
+            if max < 1e-32 || !max.is_finite() {
+                return [0; 4];
+            }
+            let exp = max.log2().floor() as i32 + 1;
+            let scale = 256.0 / 2f32.powi(exp);
+            [
+                (r * scale).round().min(255.0) as u8,
+                (g * scale).round().min(255.0) as u8,
+                (b * scale).round().min(255.0) as u8,
+                (exp + 128).clamp(0, 255) as u8,
+            ]
+        })
+        .collect()
+}
+
+pub(crate) fn decode(rgbe: &[u8]) -> Vec<f32> {
+    rgbe.chunks_exact(4)
+        .flat_map(|p| {
+            if p[3] == 0 {
+                return [0.0; 3];
+            }
+            let scale = 2f32.powi(p[3] as i32 - 136);
+            [p[0], p[1], p[2]].map(|c| c as f32 * scale)
+        })
+        .collect()
+}
@@ -0,0 +1,72 @@
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum Wrap {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
+)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct Sampler {
+    wrap_s: Wrap,
+    wrap_t: Wrap,
+    min_filter: Filter,
+    mag_filter: Filter,
+    mip_filter: Option<Filter>,
+}
+
+impl Sampler {
+    pub const fn new(
+        wrap_s: Wrap,
+        wrap_t: Wrap,
+        min_filter: Filter,
+        mag_filter: Filter,
+        mip_filter: Option<Filter>,
+    ) -> Self {
+        Self {
+            wrap_s,
+            wrap_t,
+            min_filter,
+            mag_filter,
+            mip_filter,
+        }
+    }
+    pub const fn wrap_s(&self) -> Wrap {
+        self.wrap_s
+    }
+    pub const fn wrap_t(&self) -> Wrap {
+        self.wrap_t
+    }
+    pub const fn min_filter(&self) -> Filter {
+        self.min_filter
+    }
+    pub const fn mag_filter(&self) -> Filter {
+        self.mag_filter
+    }
+    pub const fn mip_filter(&self) -> Option<Filter> {
+        self.mip_filter
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new(
+            Wrap::Repeat,
+            Wrap::Repeat,
+            Filter::Linear,
+            Filter::Linear,
+            Some(Filter::Linear),
+        )
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{Compression, Section};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SaveOptions {
+    compression: Compression,
+    // per section overrides, indexed by section id
+    sections: [Option<Compression>; Section::ALL.len()],
+}
+
+impl SaveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+    // e.g. `Compression::None` for images so they can be mapped in place
+    pub const fn with_section(mut self, section: Section, compression: Compression) -> Self {
+        self.sections[section.id() as usize] = Some(compression);
+        self
+    }
+    pub const fn compression(&self, section: Section) -> Compression {
+        match self.sections[section.id() as usize] {
+            Some(compression) => compression,
+            None => self.compression,
+        }
+    }
+}
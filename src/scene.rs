@@ -1,14 +1,22 @@
-use crate::{file::ArchivedFile, file::File, Image, Light, Material, Mesh};
+use rkyv::util::AlignedVec;
+
+use crate::{
+    container, ibl, Camera, ColorSpace, Environment, Error, Image, ImageFormat, ImageKind, Light,
+    Material, Mesh, MipFilter, ProbeVolume, SaveOptions, SceneView, Section, ValidationError,
+};
 
 #[cfg(feature = "safe")]
-use crate::{material::ArchivedMaterial, mesh::ArchivedMesh};
+use crate::{file::File, legacy::SceneV1};
 
-#[derive(Debug, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+#[derive(Debug)]
 pub struct Scene {
     meshes: Option<Vec<Mesh>>,
     materials: Option<Vec<Material>>,
     lights: Option<Vec<Light>>,
     images: Option<Vec<Image>>,
+    cameras: Option<Vec<Camera>>,
+    environment: Option<Environment>,
+    probe_volumes: Option<Vec<ProbeVolume>>,
 }
 
 impl Scene {
@@ -24,17 +32,55 @@ impl Scene {
     pub fn images(&self) -> Option<&[Image]> {
         self.images.as_deref()
     }
-    pub fn decode_images(&self) -> Option<Vec<Vec<u8>>> {
-        self.images
-            .as_ref()
-            .map(|images| {
-                images
-                    .into_iter()
-                    .map(Image::decode)
-                    .map(Result::ok)
-                    .collect()
-            })
-            .flatten()
+    pub fn cameras(&self) -> Option<&[Camera]> {
+        self.cameras.as_deref()
+    }
+    pub const fn environment(&self) -> Option<&Environment> {
+        self.environment.as_ref()
+    }
+    pub fn probe_volumes(&self) -> Option<&[ProbeVolume]> {
+        self.probe_volumes.as_deref()
+    }
+    pub fn decode_images(&self) -> Vec<Result<Vec<u8>, Error>> {
+        self.images.iter().flatten().map(Image::decode).collect()
+    }
+    #[cfg(feature = "rayon")]
+    pub fn par_decode_images(&self) -> Vec<Result<Vec<u8>, Error>> {
+        use rayon::prelude::*;
+        let images = self.images.as_deref().unwrap_or_default();
+        images.par_iter().map(Image::decode).collect()
+    }
+    // images used by the given materials, sorted and without duplicates
+    pub fn material_images(&self, materials: &[usize]) -> Vec<u32> {
+        let all = self.materials.as_deref().unwrap_or_default();
+        used_images(
+            materials
+                .iter()
+                .filter_map(|m| all.get(*m))
+                .flat_map(Material::images),
+        )
+    }
+    pub fn decode_material_images(
+        &self,
+        materials: &[usize],
+    ) -> Vec<(u32, Result<Vec<u8>, Error>)> {
+        let images = self.images.as_deref().unwrap_or_default();
+        self.material_images(materials)
+            .into_iter()
+            .filter_map(|i| Some((i, images.get(i as usize)?.decode())))
+            .collect()
+    }
+    #[cfg(feature = "rayon")]
+    pub fn par_decode_material_images(
+        &self,
+        materials: &[usize],
+    ) -> Vec<(u32, Result<Vec<u8>, Error>)> {
+        use rayon::prelude::*;
+        let images = self.images.as_deref().unwrap_or_default();
+        self.material_images(materials)
+            .into_par_iter()
+            .filter_map(|i| Some((i, images.get(i as usize)?.decode())))
+            .collect()
     }
     fn add<T>(items: &mut Option<Vec<T>>, item: T) {
         if let Some(items) = items {
@@ -43,17 +89,221 @@ impl Scene {
             *items = Some(vec![item]);
         }
     }
-    pub fn add_mesh(&mut self, mesh: Mesh) {
+    fn first(errors: Vec<ValidationError>) -> Result<(), ValidationError> {
+        errors.into_iter().next().map_or(Ok(()), Err)
+    }
+    pub fn add_mesh(&mut self, mesh: Mesh) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        mesh.validate(
+            self.meshes.as_ref().map_or(0, Vec::len),
+            self.materials.as_ref().map_or(0, Vec::len),
+            self.images.as_ref().map_or(0, Vec::len),
+            &mut errors,
+        );
+        mesh.validate_uv_sets(
+            self.meshes.as_ref().map_or(0, Vec::len),
+            self.materials.as_deref().unwrap_or_default(),
+            &mut errors,
+        );
+        Self::first(errors)?;
         Self::add(&mut self.meshes, mesh);
+        Ok(())
     }
-    pub fn add_material(&mut self, material: Material) {
+    pub fn add_material(&mut self, material: Material) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        material.validate(
+            self.materials.as_ref().map_or(0, Vec::len),
+            self.images.as_ref().map_or(0, Vec::len),
+            &mut errors,
+        );
+        Self::first(errors)?;
         Self::add(&mut self.materials, material);
+        // retagged from every material so an image shared as color and data
+        // stays sRGB whichever material came first
+        self.tag_color_spaces();
+        Ok(())
     }
-    pub fn add_light(&mut self, light: Light) {
+    pub fn add_light(&mut self, light: Light) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        light.validate(self.lights.as_ref().map_or(0, Vec::len), &mut errors);
+        Self::first(errors)?;
         Self::add(&mut self.lights, light);
+        Ok(())
     }
-    pub fn add_image(&mut self, image: Image) {
+    pub fn add_image(&mut self, image: Image) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        image.validate(self.images.as_ref().map_or(0, Vec::len), &mut errors);
+        Self::first(errors)?;
         Self::add(&mut self.images, image);
+        Ok(())
+    }
+    pub fn add_camera(&mut self, camera: Camera) {
+        Self::add(&mut self.cameras, camera);
+    }
+    pub fn add_probe_volume(&mut self, volume: ProbeVolume) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        volume.validate(self.probe_volumes.as_ref().map_or(0, Vec::len), &mut errors);
+        Self::first(errors)?;
+        Self::add(&mut self.probe_volumes, volume);
+        Ok(())
+    }
+    pub fn set_environment(&mut self, environment: Environment) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        environment.validate(self.images.as_ref().map_or(0, Vec::len), &mut errors);
+        Self::first(errors)?;
+        self.environment = Some(environment);
+        Ok(())
+    }
+    // bakes the irradiance SH and a `width` wide prefiltered specular image
+    // (stored as a new image, or over the previous bake), `samples` GGX
+    // samples per texel, 64 to 256 is plenty. does nothing without an environment
+    pub fn bake_environment(&mut self, width: u32, samples: u32) -> Result<(), Error> {
+        let Some(environment) = &mut self.environment else {
+            return Ok(());
+        };
+        let mut errors = Vec::new();
+        environment.validate(self.images.as_ref().map_or(0, Vec::len), &mut errors);
+        let Some(images) = self.images.as_mut().filter(|_| errors.is_empty()) else {
+            return Err(Error::Invalid(errors));
+        };
+        let image = &images[environment.image() as usize];
+        if image.kind() != ImageKind::D2 {
+            return Err(Error::NotEquirect);
+        }
+        let source = image.surface(0, image.color_space() == ColorSpace::Srgb)?;
+        let irradiance = ibl::irradiance_sh(&source);
+        let specular =
+            Image::from_hdr_levels(&ibl::prefilter(source, width as usize, samples.max(1)));
+        let index = match environment.specular() {
+            // never overwrite the sky itself
+            Some(i) if i != environment.image() => {
+                images[i as usize] = specular;
+                i
+            }
+            _ => {
+                let Ok(index) = u32::try_from(images.len()) else {
+                    return Err(Error::Invalid(vec![ValidationError::TooManyItems {
+                        section: Section::Images,
+                        len: images.len() + 1,
+                    }]));
+                };
+                images.push(specular);
+                index
+            }
+        };
+        environment.set_baked(irradiance, index);
+        Ok(())
+    }
+    fn tag_images(images: &mut [Image], usages: impl Iterator<Item = (u32, ColorSpace)>) {
+        // an image used both as color and as data stays sRGB
+        let mut usages = usages.collect::<Vec<_>>();
+        usages.sort_by_key(|(_, c)| *c == ColorSpace::Srgb);
+        for (image, color_space) in usages {
+            if let Some(image) = images.get_mut(image as usize) {
+                image.set_color_space(color_space);
+            }
+        }
+    }
+    pub(crate) fn tag_color_spaces(&mut self) {
+        if let (Some(materials), Some(images)) = (&self.materials, &mut self.images) {
+            Self::tag_images(
+                images,
+                materials.iter().flat_map(Material::image_color_spaces),
+            );
+        }
+    }
+    fn normal_images(&self) -> Vec<u32> {
+        self.materials
+            .iter()
+            .flatten()
+            .flat_map(Material::normal_images)
+            .collect()
+    }
+    // the baked specular image keeps its prefiltered chain
+    pub fn generate_mips(&mut self, filter: MipFilter) -> Result<(), Error> {
+        let normals = self.normal_images();
+        let specular = self.environment.as_ref().and_then(Environment::specular);
+        for (i, image) in self.images.iter_mut().flatten().enumerate() {
+            if specular == Some(i as u32) {
+                continue;
+            }
+            if normals.contains(&(i as u32)) {
+                image.generate_normal_mips(filter)?;
+            } else {
+                image.generate_mips(filter)?;
+            }
+        }
+        Ok(())
+    }
+    // normal maps always use `Bc5`, HDR images stay RGBE
+    pub fn compress_images(&mut self, format: ImageFormat) -> Result<(), Error> {
+        let normals = self.normal_images();
+        for (i, image) in self.images.iter_mut().flatten().enumerate() {
+            if image.format().is_hdr() {
+                continue;
+            }
+            if format.is_block_compressed() && normals.contains(&(i as u32)) {
+                image.compress(ImageFormat::Bc5)?;
+            } else {
+                image.compress(format)?;
+            }
+        }
+        Ok(())
+    }
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = self.validate_references();
+        for (i, mesh) in self.meshes.iter().flatten().enumerate() {
+            mesh.validate_uv_sets(
+                i,
+                self.materials.as_deref().unwrap_or_default(),
+                &mut errors,
+            );
+        }
+        errors
+    }
+    // everything but the texture uv sets, which v1 files never recorded
+    pub(crate) fn validate_references(&self) -> Vec<ValidationError> {
+        let images = self.images.as_ref().map_or(0, Vec::len);
+        let materials = self.materials.as_ref().map_or(0, Vec::len);
+        let mut errors = Vec::new();
+        for (i, image) in self.images.iter().flatten().enumerate() {
+            image.validate(i, &mut errors);
+        }
+        for (i, material) in self.materials.iter().flatten().enumerate() {
+            material.validate(i, images, &mut errors);
+        }
+        for (i, mesh) in self.meshes.iter().flatten().enumerate() {
+            mesh.validate(i, materials, images, &mut errors);
+        }
+        for (i, light) in self.lights.iter().flatten().enumerate() {
+            light.validate(i, &mut errors);
+        }
+        if let Some(environment) = &self.environment {
+            environment.validate(images, &mut errors);
+        }
+        for (i, volume) in self.probe_volumes.iter().flatten().enumerate() {
+            volume.validate(i, &mut errors);
+        }
+        errors
+    }
+    pub(crate) const fn from_parts(
+        meshes: Option<Vec<Mesh>>,
+        materials: Option<Vec<Material>>,
+        lights: Option<Vec<Light>>,
+        images: Option<Vec<Image>>,
+        cameras: Option<Vec<Camera>>,
+        environment: Option<Environment>,
+        probe_volumes: Option<Vec<ProbeVolume>>,
+    ) -> Self {
+        Self {
+            meshes,
+            materials,
+            lights,
+            images,
+            cameras,
+            environment,
+            probe_volumes,
+        }
     }
     pub fn new(
         meshes: Option<Vec<Mesh>>,
@@ -61,138 +311,142 @@ impl Scene {
         lights: Option<Vec<Light>>,
         images: Option<Vec<Image>>,
     ) -> Option<Self> {
+        let mut scene = Self::from_parts(meshes, materials, lights, images, None, None, None);
+        scene.tag_color_spaces();
         #[cfg(feature = "safe")]
         {
-            let tex = images.as_ref().map(Vec::len).unwrap_or(0);
-            let mat = materials.as_ref().map(Vec::len).unwrap_or(0);
-            if !materials
-                .as_ref()
-                .map(|m| {
-                    m.iter()
-                        .filter_map(Material::max_texture_idx)
-                        .max()
-                        .map(|x| x as usize)
-                        .map(|max| max < tex)
-                })
-                .flatten()
-                .unwrap_or(true)
-            {
-                println!("[ERROR]: images len is less than image index in material!");
-                None
-            } else if !meshes.as_ref().is_some_and(|m| {
-                m.iter()
-                    .filter_map(Mesh::material)
-                    .map(|x| x as usize)
-                    .all(|m| m < mat)
-            }) {
-                println!("[ERROR]: materials len is less than material index in mesh!");
-                None
-            } else if meshes
-                .as_ref()
-                .is_some_and(|m| m.iter().any(Mesh::isnt_right))
-            {
-                println!("[ERROR]: mesh attributes DOESNT have the same len!");
-                None
-            } else {
-                Some(Self {
-                    meshes,
-                    materials,
-                    lights,
-                    images,
-                })
+            let errors = scene.validate();
+            if !errors.is_empty() {
+                for e in errors {
+                    println!("[ERROR]: {e}!");
+                }
+                return None;
             }
         }
-        #[cfg(not(feature = "safe"))]
+        Some(scene)
+    }
+    pub fn load(data: &[u8]) -> Result<Self, Error> {
+        SceneView::load(data)?.to_scene()
+    }
+    // only `sections` are decompressed, checked and deserialized, the rest
+    // stay `None`
+    pub fn load_sections(data: &[u8], sections: &[Section]) -> Result<Self, Error> {
+        SceneView::load_sections(data, sections)?.to_scene()
+    }
+    // v1 files have no checksum, so they are only read with bytecheck
+    #[cfg(feature = "safe")]
+    pub(crate) fn load_v1(data: &[u8]) -> Result<Self, Error> {
+        let file = File::load(data)?;
+        rkyv::from_bytes::<SceneV1, rkyv::rancor::Error>(&file)?.migrate()
+    }
+    #[cfg(not(feature = "safe"))]
+    pub(crate) fn load_v1(_data: &[u8]) -> Result<Self, Error> {
+        Err(Error::LegacyRequiresSafe)
+    }
+    // one serialized buffer per present section, with its item count
+    pub(crate) fn chunks(&self) -> Result<Vec<(Section, u32, AlignedVec)>, Error> {
+        fn chunk<T>(
+            chunks: &mut Vec<(Section, u32, AlignedVec)>,
+            section: Section,
+            items: Option<&T>,
+            count: impl FnOnce(&T) -> usize,
+        ) -> Result<(), Error>
+        where
+            T: for<'a> rkyv::Serialize<
+                rkyv::api::high::HighSerializer<
+                    AlignedVec,
+                    rkyv::ser::allocator::ArenaHandle<'a>,
+                    rkyv::rancor::Error,
+                >,
+            >,
         {
-            Some(Self {
-                meshes,
-                materials,
-                lights,
-                images,
-            })
+            if let Some(items) = items {
+                let len = count(items);
+                let count = u32::try_from(len).map_err(|_| {
+                    Error::Invalid(vec![ValidationError::TooManyItems { section, len }])
+                })?;
+                chunks.push((section, count, rkyv::to_bytes(items)?));
+            }
+            Ok(())
         }
+        let mut chunks = Vec::new();
+        chunk(&mut chunks, Section::Meshes, self.meshes.as_ref(), Vec::len)?;
+        chunk(
+            &mut chunks,
+            Section::Materials,
+            self.materials.as_ref(),
+            Vec::len,
+        )?;
+        chunk(&mut chunks, Section::Lights, self.lights.as_ref(), Vec::len)?;
+        chunk(&mut chunks, Section::Images, self.images.as_ref(), Vec::len)?;
+        chunk(
+            &mut chunks,
+            Section::Cameras,
+            self.cameras.as_ref(),
+            Vec::len,
+        )?;
+        chunk(
+            &mut chunks,
+            Section::Environment,
+            self.environment.as_ref(),
+            |_| 1,
+        )?;
+        chunk(
+            &mut chunks,
+            Section::ProbeVolumes,
+            self.probe_volumes.as_ref(),
+            Vec::len,
+        )?;
+        Ok(chunks)
+    }
+    // reads everything first, sections are addressed by offset
+    pub fn read_from(mut reader: impl std::io::Read) -> Result<Self, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::load(&data)
     }
-    pub fn load(data: &[u8]) -> Option<Self> {
-        let mut bytes = rkyv::util::AlignedVec::<16>::new();
-        bytes.extend_from_slice(data);
+    pub fn write_to(&self, writer: impl std::io::Write) -> Result<(), Error> {
+        self.write_to_with(writer, &SaveOptions::default())
+    }
+    pub fn write_to_with(
+        &self,
+        writer: impl std::io::Write,
+        options: &SaveOptions,
+    ) -> Result<(), Error> {
         #[cfg(feature = "safe")]
         {
-            let file = File::load(
-                rkyv::access::<ArchivedFile, rkyv::rancor::Error>(&bytes)
-                    .map_err(|e| println!("[ERROR]: {e:?}"))
-                    .ok()?,
-            )?;
-            let data = rkyv::access::<ArchivedScene, rkyv::rancor::Error>(&file)
-                .map_err(|e| println!("[ERROR]: {e:?}"))
-                .ok()?;
-            let tex = data
-                .images
-                .as_ref()
-                .map(rkyv::vec::ArchivedVec::len)
-                .unwrap_or(0);
-            let mat = data
-                .materials
-                .as_ref()
-                .map(rkyv::vec::ArchivedVec::len)
-                .unwrap_or(0);
-            if !data
-                .materials
-                .as_ref()
-                .map(|m| {
-                    m.iter()
-                        .filter_map(ArchivedMaterial::max_texture_idx)
-                        .max()
-                        .map(|x| x as usize)
-                        .map(|max| max < tex)
-                })
-                .flatten()
-                .unwrap_or(true)
-            {
-                println!("[ERROR]: images len is less than image index in material!");
-                None
-            } else if !data.meshes.as_ref().is_some_and(|m| {
-                m.iter()
-                    .filter_map(ArchivedMesh::material)
-                    .map(|x| x as usize)
-                    .all(|m| m < mat)
-            }) {
-                println!("[ERROR]: materials len is less than material index in mesh!");
-                None
-            } else if data
-                .meshes
-                .as_ref()
-                .is_some_and(|m| m.iter().any(ArchivedMesh::isnt_right))
-            {
-                println!("[ERROR]: mesh attributes DOESNT have the same len!");
-                None
-            } else {
-                Some(
-                    rkyv::deserialize::<Self, rkyv::rancor::Error>(data)
-                        .map_err(|e| println!("[ERROR]: {e:?}"))
-                        .ok()?,
-                )
+            let errors = self.validate();
+            if !errors.is_empty() {
+                return Err(Error::Invalid(errors));
             }
         }
-        #[cfg(not(feature = "safe"))]
-        {
-            let file = File::load(unsafe { rkyv::access_unchecked::<ArchivedFile>(&bytes) })?;
-            let data = unsafe { rkyv::access_unchecked::<ArchivedScene>(&file) };
-            Some(
-                rkyv::deserialize::<Self, rkyv::rancor::Error>(data)
-                    .map_err(|e| println!("[ERROR]: {e:?}"))
-                    .ok()?,
-            )
-        }
-    }
-    pub fn save(&self, path: &str) -> Result<(), ()> {
-        use std::io::Write;
-        let mut file = std::fs::File::create(path).map_err(|e| println!("[ERROR]: {e:?}"))?;
-        let bytes =
-            rkyv::to_bytes::<rkyv::rancor::Error>(self).map_err(|e| println!("[ERROR]: {e:?}"))?;
-        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&File::save(&bytes))
-            .map_err(|e| println!("[ERROR]: {e:?}"))?;
-        file.write_all(&bytes)
-            .map_err(|e| println!("[ERROR]: {e:?}"))?;
+        container::write(&self.chunks()?, options, writer)
+    }
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes_with(&SaveOptions::default())
+    }
+    pub fn to_bytes_with(&self, options: &SaveOptions) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        self.write_to_with(&mut bytes, options)?;
+        Ok(bytes)
+    }
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        self.save_with(path, &SaveOptions::default())
+    }
+    pub fn save_with(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        options: &SaveOptions,
+    ) -> Result<(), Error> {
+        // serialized first so a failed save leaves an existing file alone
+        std::fs::write(path, self.to_bytes_with(options)?)?;
         Ok(())
     }
 }
+
+pub(crate) fn used_images(images: impl Iterator<Item = u32>) -> Vec<u32> {
+    let mut images = images.collect::<Vec<_>>();
+    images.sort_unstable();
+    images.dedup();
+    images
+}
@@ -0,0 +1,29 @@
+// independently stored parts of a file, see `Scene::load_sections`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Meshes,
+    Materials,
+    Lights,
+    Images,
+    Cameras,
+    Environment,
+    ProbeVolumes,
+}
+
+impl Section {
+    pub const ALL: [Self; 7] = [
+        Self::Meshes,
+        Self::Materials,
+        Self::Lights,
+        Self::Images,
+        Self::Cameras,
+        Self::Environment,
+        Self::ProbeVolumes,
+    ];
+    pub(crate) const fn id(self) -> u32 {
+        self as u32
+    }
+    pub(crate) fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+}
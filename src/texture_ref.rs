@@ -0,0 +1,117 @@
+use crate::{ImageHandle, Sampler};
+
+#[derive(Debug, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct TextureTransform {
+    offset: [f32; 2],
+    scale: [f32; 2],
+    rotation: f32,
+}
+
+impl TextureTransform {
+    pub const fn new(offset: [f32; 2], scale: [f32; 2], rotation: f32) -> Self {
+        Self {
+            offset,
+            scale,
+            rotation,
+        }
+    }
+    pub const fn offset(&self) -> [f32; 2] {
+        self.offset
+    }
+    pub const fn scale(&self) -> [f32; 2] {
+        self.scale
+    }
+    pub const fn rotation(&self) -> f32 {
+        self.rotation
+    }
+    // row-major `translation * rotation * scale`, same as KHR_texture_transform
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let [sx, sy] = self.scale;
+        let [ox, oy] = self.offset;
+        [
+            [cos * sx, sin * sy, ox],
+            [-sin * sx, cos * sy, oy],
+            [0.0, 0.0, 1.0],
+        ]
+    }
+    pub fn apply(&self, [u, v]: [f32; 2]) -> [f32; 2] {
+        let [a, b, _] = self.matrix();
+        [a[0] * u + a[1] * v + a[2], b[0] * u + b[1] * v + b[2]]
+    }
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self::new([0.0; 2], [1.0; 2], 0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
+pub struct TextureRef {
+    image: u32,
+    sampler: Sampler,
+    uv_set: u32,
+    transform: TextureTransform,
+    // set when made from an `ImageHandle`, never stored
+    #[rkyv(with = rkyv::with::Skip)]
+    builder: Option<u32>,
+}
+
+impl ArchivedTextureRef {
+    pub(crate) const fn image(&self) -> u32 {
+        self.image.to_native()
+    }
+    #[cfg(feature = "safe")]
+    pub(crate) const fn uv_set(&self) -> u32 {
+        self.uv_set.to_native()
+    }
+}
+
+impl TextureRef {
+    pub fn new(image: u32) -> Self {
+        Self {
+            image,
+            sampler: Sampler::default(),
+            uv_set: 0,
+            transform: TextureTransform::default(),
+            builder: None,
+        }
+    }
+    pub const fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+    pub const fn with_uv_set(mut self, uv_set: u32) -> Self {
+        self.uv_set = uv_set;
+        self
+    }
+    pub const fn with_transform(mut self, transform: TextureTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+    pub const fn image(&self) -> u32 {
+        self.image
+    }
+    pub const fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+    pub const fn uv_set(&self) -> u32 {
+        self.uv_set
+    }
+    pub const fn transform(&self) -> &TextureTransform {
+        &self.transform
+    }
+    pub(crate) const fn builder(&self) -> Option<u32> {
+        self.builder
+    }
+}
+
+impl From<ImageHandle> for TextureRef {
+    fn from(image: ImageHandle) -> Self {
+        Self {
+            builder: Some(image.builder()),
+            ..Self::new(image.index())
+        }
+    }
+}
@@ -1,35 +1,42 @@
+use crate::{texture_ref::ArchivedTextureRef, TextureRef};
+
 #[derive(Debug, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize)]
 pub enum Uniform {
-    Color([f32; 3]),
-    Texture(u8),
+    Color([f32; 4]),
+    Texture {
+        texture: TextureRef,
+        factor: [f32; 4],
+    },
 }
 
-#[cfg(feature = "safe")]
 impl ArchivedUniform {
-    pub(crate) const fn texture(&self) -> Option<u8> {
+    pub(crate) const fn texture(&self) -> Option<&ArchivedTextureRef> {
         match self {
-            Self::Texture(t) => Some(*t),
+            Self::Texture { texture, .. } => Some(texture),
             _ => None,
         }
     }
 }
 
 impl Uniform {
-    pub const fn new_color(color: [f32; 3]) -> Self {
+    pub const fn new_color(color: [f32; 4]) -> Self {
         Self::Color(color)
     }
-    pub const fn new_texture(data: u8) -> Self {
-        Self::Texture(data)
+    pub const fn new_texture(texture: TextureRef) -> Self {
+        Self::Texture {
+            texture,
+            factor: [1.0; 4],
+        }
     }
-    pub const fn color(&self) -> Option<&[f32; 3]> {
+    pub const fn color(&self) -> &[f32; 4] {
         match self {
-            Self::Color(c) => Some(c),
-            _ => None,
+            Self::Color(c) => c,
+            Self::Texture { factor, .. } => factor,
         }
     }
-    pub const fn texture(&self) -> Option<u8> {
+    pub const fn texture(&self) -> Option<&TextureRef> {
         match self {
-            Self::Texture(t) => Some(*t),
+            Self::Texture { texture, .. } => Some(texture),
             _ => None,
         }
     }
@@ -37,6 +44,6 @@ impl Uniform {
 
 impl Default for Uniform {
     fn default() -> Self {
-        Self::Color([1.0; 3])
+        Self::Color([1.0; 4])
     }
 }
@@ -0,0 +1,305 @@
+use rkyv::{util::AlignedVec, vec::ArchivedVec};
+
+use crate::{
+    container::{self, Container},
+    scene::used_images,
+    ArchivedCamera, ArchivedEnvironment, ArchivedImage, ArchivedLight, ArchivedMaterial,
+    ArchivedMesh, ArchivedProbeVolume, Compression, Error, Scene, Section,
+};
+
+enum Bytes {
+    Owned(AlignedVec),
+    // range of `SceneView::map`
+    #[cfg(feature = "mmap")]
+    Mapped(std::ops::Range<usize>),
+}
+
+// keeps the decompressed sections around and reads them in place, so images
+// can be decoded one by one instead of deserializing the whole scene
+pub struct SceneView {
+    #[cfg(feature = "mmap")]
+    map: Option<memmap2::Mmap>,
+    sections: [Option<Bytes>; Section::ALL.len()],
+    // item counts from the table of contents, known even for skipped sections
+    counts: [usize; Section::ALL.len()],
+    // how each section was stored, `None` for v1 files and unknown codecs
+    compressions: [Option<Compression>; Section::ALL.len()],
+}
+
+impl std::fmt::Debug for SceneView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = Section::ALL
+            .into_iter()
+            .filter_map(|s| self.bytes(s))
+            .map(<[u8]>::len)
+            .sum::<usize>();
+        f.write_fmt(format_args!("SceneView: [{bytes} bytes]"))
+    }
+}
+
+fn deserialize<T>(archived: Option<&T::Archived>) -> Result<Option<T>, Error>
+where
+    T: rkyv::Archive,
+    T::Archived: rkyv::Deserialize<T, rkyv::api::high::HighDeserializer<rkyv::rancor::Error>>,
+{
+    Ok(archived
+        .map(rkyv::deserialize::<T, rkyv::rancor::Error>)
+        .transpose()?)
+}
+
+#[cfg(feature = "safe")]
+fn check<T>(bytes: Option<&[u8]>) -> Result<(), Error>
+where
+    T: rkyv::Portable
+        + for<'a> rkyv::bytecheck::CheckBytes<rkyv::api::high::HighValidator<'a, rkyv::rancor::Error>>,
+{
+    if let Some(bytes) = bytes {
+        rkyv::access::<T, rkyv::rancor::Error>(bytes)?;
+    }
+    Ok(())
+}
+
+impl SceneView {
+    pub fn load(data: &[u8]) -> Result<Self, Error> {
+        Self::load_sections(data, &Section::ALL)
+    }
+    // sections missing from the file or not asked for read as empty
+    pub fn load_sections(data: &[u8], sections: &[Section]) -> Result<Self, Error> {
+        if !container::is_container(data) {
+            // v1 files are migrated (and validated) then split in memory
+            let mut view = Self::new([0; Section::ALL.len()]);
+            for (section, count, bytes) in Scene::load_v1(data)?.chunks()? {
+                view.counts[section.id() as usize] = count as usize;
+                if sections.contains(&section) {
+                    view.sections[section.id() as usize] = Some(Bytes::Owned(bytes));
+                }
+            }
+            return Ok(view);
+        }
+        let container = Container::parse(data)?;
+        let mut view = Self::new(Section::ALL.map(|s| container.count(s)));
+        view.compressions = Section::ALL.map(|s| container.entry(s)?.compression);
+        for section in sections {
+            if let Some(entry) = container.entry(*section) {
+                view.sections[section.id() as usize] = Some(Bytes::Owned(container.read(entry)?));
+            }
+        }
+        #[cfg(feature = "safe")]
+        view.validate()?;
+        Ok(view)
+    }
+    // uncompressed sections are read straight from the mapped file, the others
+    // are decompressed as usual
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::open_mmap_sections(path, &Section::ALL)
+    }
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap_sections(
+        path: impl AsRef<std::path::Path>,
+        sections: &[Section],
+    ) -> Result<Self, Error> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the map is only read, and only through the view. like any
+        // file mapping it assumes no other process truncates or rewrites the
+        // file while the view is alive, which can't be checked from here. the
+        // checksums and validation at load still catch a file that changed
+        // before it was mapped
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Self::from_mmap_sections(map, sections)
+    }
+    #[cfg(feature = "mmap")]
+    fn from_mmap_sections(map: memmap2::Mmap, sections: &[Section]) -> Result<Self, Error> {
+        if !container::is_container(&map) {
+            return Self::load_sections(&map, sections);
+        }
+        let container = Container::parse(&map)?;
+        let mut view = Self::new(Section::ALL.map(|s| container.count(s)));
+        view.compressions = Section::ALL.map(|s| container.entry(s)?.compression);
+        for section in sections {
+            if let Some(entry) = container.entry(*section) {
+                view.sections[section.id() as usize] = Some(match container.in_place(entry)? {
+                    Some(range) => Bytes::Mapped(range),
+                    None => Bytes::Owned(container.read(entry)?),
+                });
+            }
+        }
+        drop(container);
+        view.map = Some(map);
+        #[cfg(feature = "safe")]
+        view.validate()?;
+        Ok(view)
+    }
+    fn new(counts: [usize; Section::ALL.len()]) -> Self {
+        Self {
+            #[cfg(feature = "mmap")]
+            map: None,
+            sections: Default::default(),
+            counts,
+            compressions: Default::default(),
+        }
+    }
+    fn bytes(&self, section: Section) -> Option<&[u8]> {
+        match self.sections[section.id() as usize].as_ref()? {
+            Bytes::Owned(bytes) => Some(bytes),
+            #[cfg(feature = "mmap")]
+            Bytes::Mapped(range) => self.map.as_ref()?.get(range.clone()),
+        }
+    }
+    // true when the section is read from the mapped file without a copy
+    pub fn is_mapped(&self, section: Section) -> bool {
+        #[cfg(feature = "mmap")]
+        if let Some(Bytes::Mapped(_)) = self.sections[section.id() as usize] {
+            return true;
+        }
+        let _ = section;
+        false
+    }
+    pub const fn count(&self, section: Section) -> usize {
+        self.counts[section.id() as usize]
+    }
+    pub const fn compression(&self, section: Section) -> Option<Compression> {
+        self.compressions[section.id() as usize]
+    }
+    fn section<T: rkyv::Portable>(&self, section: Section) -> Option<&T> {
+        let bytes = self.bytes(section)?;
+        // checked once in `load_sections` when the safe feature is enabled
+        Some(unsafe { rkyv::access_unchecked::<T>(bytes) })
+    }
+    fn items<T: rkyv::Portable>(&self, section: Section) -> &[T] {
+        self.section::<ArchivedVec<T>>(section)
+            .map_or(&[], ArchivedVec::as_slice)
+    }
+    pub fn meshes(&self) -> &[ArchivedMesh] {
+        self.items(Section::Meshes)
+    }
+    pub fn materials(&self) -> &[ArchivedMaterial] {
+        self.items(Section::Materials)
+    }
+    pub fn lights(&self) -> &[ArchivedLight] {
+        self.items(Section::Lights)
+    }
+    pub fn images(&self) -> &[ArchivedImage] {
+        self.items(Section::Images)
+    }
+    pub fn cameras(&self) -> &[ArchivedCamera] {
+        self.items(Section::Cameras)
+    }
+    pub fn environment(&self) -> Option<&ArchivedEnvironment> {
+        self.section(Section::Environment)
+    }
+    pub fn probe_volumes(&self) -> &[ArchivedProbeVolume] {
+        self.items(Section::ProbeVolumes)
+    }
+    pub fn decode_images(&self) -> Vec<Result<Vec<u8>, Error>> {
+        self.images().iter().map(ArchivedImage::decode).collect()
+    }
+    #[cfg(feature = "rayon")]
+    pub fn par_decode_images(&self) -> Vec<Result<Vec<u8>, Error>> {
+        use rayon::prelude::*;
+        self.images()
+            .par_iter()
+            .map(ArchivedImage::decode)
+            .collect()
+    }
+    pub fn material_images(&self, materials: &[usize]) -> Vec<u32> {
+        let all = self.materials();
+        used_images(
+            materials
+                .iter()
+                .filter_map(|m| all.get(*m))
+                .flat_map(|m| m.images()),
+        )
+    }
+    pub fn decode_material_images(
+        &self,
+        materials: &[usize],
+    ) -> Vec<(u32, Result<Vec<u8>, Error>)> {
+        let images = self.images();
+        self.material_images(materials)
+            .into_iter()
+            .filter_map(|i| Some((i, images.get(i as usize)?.decode())))
+            .collect()
+    }
+    #[cfg(feature = "rayon")]
+    pub fn par_decode_material_images(
+        &self,
+        materials: &[usize],
+    ) -> Vec<(u32, Result<Vec<u8>, Error>)> {
+        use rayon::prelude::*;
+        let images = self.images();
+        self.material_images(materials)
+            .into_par_iter()
+            .filter_map(|i| Some((i, images.get(i as usize)?.decode())))
+            .collect()
+    }
+    pub fn to_scene(&self) -> Result<Scene, Error> {
+        Ok(Scene::from_parts(
+            deserialize(self.section(Section::Meshes))?,
+            deserialize(self.section(Section::Materials))?,
+            deserialize(self.section(Section::Lights))?,
+            deserialize(self.section(Section::Images))?,
+            deserialize(self.section(Section::Cameras))?,
+            deserialize(self.section(Section::Environment))?,
+            deserialize(self.section(Section::ProbeVolumes))?,
+        ))
+    }
+    // references are checked against the table of contents counts, so a
+    // section can be validated without loading the ones it points into
+    #[cfg(feature = "safe")]
+    fn validate(&self) -> Result<(), Error> {
+        let bytes = |section: Section| self.bytes(section);
+        check::<ArchivedVec<ArchivedMesh>>(bytes(Section::Meshes))?;
+        check::<ArchivedVec<ArchivedMaterial>>(bytes(Section::Materials))?;
+        check::<ArchivedVec<ArchivedLight>>(bytes(Section::Lights))?;
+        check::<ArchivedVec<ArchivedImage>>(bytes(Section::Images))?;
+        check::<ArchivedVec<ArchivedCamera>>(bytes(Section::Cameras))?;
+        check::<ArchivedEnvironment>(bytes(Section::Environment))?;
+        check::<ArchivedVec<ArchivedProbeVolume>>(bytes(Section::ProbeVolumes))?;
+        let mut errors = Vec::new();
+        let lens = [
+            self.meshes().len(),
+            self.materials().len(),
+            self.lights().len(),
+            self.images().len(),
+            self.cameras().len(),
+            usize::from(self.environment().is_some()),
+            self.probe_volumes().len(),
+        ];
+        for (section, len) in Section::ALL.into_iter().zip(lens) {
+            let expected = self.count(section);
+            if bytes(section).is_some() && len != expected {
+                errors.push(crate::ValidationError::SectionCount {
+                    section,
+                    expected,
+                    len,
+                });
+            }
+        }
+        let images = self.count(Section::Images);
+        let materials = self.count(Section::Materials);
+        for (i, image) in self.images().iter().enumerate() {
+            image.validate(i, &mut errors);
+        }
+        for (i, material) in self.materials().iter().enumerate() {
+            material.validate(i, images, &mut errors);
+        }
+        for (i, mesh) in self.meshes().iter().enumerate() {
+            mesh.validate(i, materials, images, &mut errors);
+            mesh.validate_uv_sets(i, self.materials(), &mut errors);
+        }
+        for (i, light) in self.lights().iter().enumerate() {
+            light.validate(i, &mut errors);
+        }
+        if let Some(environment) = self.environment() {
+            environment.validate(images, &mut errors);
+        }
+        for (i, volume) in self.probe_volumes().iter().enumerate() {
+            volume.validate(i, &mut errors);
+        }
+        if !errors.is_empty() {
+            return Err(Error::Invalid(errors));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use rnm_3d::{Image, MaterialBuilder, MeshBuilder, SceneBuilder, TextureRef, ValidationError};
+
+fn white() -> Image {
+    Image::from_raw(2, 2, &[255; 16]).unwrap()
+}
+
+#[test]
+fn foreign_handles() {
+    let mut a = SceneBuilder::new();
+    let mut b = SceneBuilder::new();
+    let image = a.add_image(white()).unwrap();
+    b.add_image(white()).unwrap();
+    let foreign = ValidationError::ForeignImage { image: 0 };
+    assert_eq!(
+        b.add_material(MaterialBuilder::new().albedo_texture(image)),
+        Err(foreign.clone())
+    );
+    assert_eq!(
+        b.add_material(MaterialBuilder::new().albedo_texture(TextureRef::new(0))),
+        Err(foreign.clone())
+    );
+    assert_eq!(b.set_environment(image), Err(foreign));
+    let material = a
+        .add_material(MaterialBuilder::new().albedo_texture(image))
+        .unwrap();
+    let mesh = || MeshBuilder::new(vec![[0.0; 3]; 3]).material(material);
+    assert_eq!(
+        b.add_mesh(mesh()),
+        Err(ValidationError::ForeignMaterial { material: 0 })
+    );
+    assert_eq!(a.add_mesh(mesh()), Ok(()));
+}
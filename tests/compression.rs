@@ -0,0 +1,38 @@
+#![cfg(feature = "compression")]
+
+use rnm_3d::{Compression, MeshBuilder, SaveOptions, SceneBuilder, SceneView, Section};
+
+// big and repetitive enough that every codec shrinks it
+fn bytes(options: &SaveOptions) -> Vec<u8> {
+    let mut builder = SceneBuilder::new();
+    builder
+        .add_mesh(MeshBuilder::new(vec![[1.0, 2.0, 3.0]; 3000]))
+        .unwrap();
+    builder.build().to_bytes_with(options).unwrap()
+}
+
+#[test]
+fn levels_are_clamped() {
+    for (asked, stored) in [
+        (Compression::Lz4Hc(0), Compression::Lz4Hc(1)),
+        (Compression::Lz4Hc(9), Compression::Lz4Hc(9)),
+        (Compression::Lz4Hc(200), Compression::Lz4Hc(12)),
+    ] {
+        let options = SaveOptions::new().with_compression(asked);
+        let view = SceneView::load(&bytes(&options)).unwrap();
+        assert_eq!(view.compression(Section::Meshes), Some(stored));
+        let scene = view.to_scene().unwrap();
+        assert_eq!(scene.meshes().unwrap()[0].positions().len(), 3000);
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_levels_are_clamped() {
+    let options = SaveOptions::new().with_compression(Compression::Zstd(100_000));
+    let view = SceneView::load(&bytes(&options)).unwrap();
+    assert_eq!(
+        view.compression(Section::Meshes),
+        Some(Compression::Zstd(22))
+    );
+}
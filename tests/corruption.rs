@@ -0,0 +1,39 @@
+use rnm_3d::{Error, MeshBuilder, Scene, SceneBuilder};
+
+fn bytes() -> Vec<u8> {
+    let mut builder = SceneBuilder::new();
+    builder
+        .add_mesh(MeshBuilder::new(vec![
+            [0.0; 3],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ]))
+        .unwrap();
+    builder.build().to_bytes().unwrap()
+}
+
+// a damaged magic sends the file down the v1 path, which must still refuse it
+// instead of reading it unchecked
+#[test]
+fn flipped_magic() {
+    let bytes = bytes();
+    for bit in 0..32 {
+        let mut bad = bytes.clone();
+        bad[bit / 8] ^= 1 << (bit % 8);
+        let err = Scene::load(&bad).unwrap_err();
+        if cfg!(not(feature = "safe")) {
+            assert!(matches!(err, Error::LegacyRequiresSafe), "{err:?}");
+        }
+    }
+}
+
+#[test]
+fn flipped_payload() {
+    let bytes = bytes();
+    let mut bad = bytes.clone();
+    *bad.last_mut().unwrap() ^= 1;
+    assert!(matches!(Scene::load(&bad), Err(Error::Checksum { .. })));
+    let mut bad = bytes;
+    bad[20] ^= 1;
+    assert!(matches!(Scene::load(&bad), Err(Error::HeaderChecksum)));
+}
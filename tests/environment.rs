@@ -0,0 +1,23 @@
+use rnm_3d::{Environment, Image, Scene};
+
+// a sky of constant radiance 1 gives an irradiance of π in every direction
+#[test]
+fn constant_irradiance() {
+    let (width, height) = (64, 32);
+    let sky = Image::from_hdr(width, height, &vec![1.0; (width * height * 3) as usize]).unwrap();
+    let mut scene = Scene::new(None, None, None, Some(vec![sky])).unwrap();
+    scene.set_environment(Environment::new(0)).unwrap();
+    scene.bake_environment(16, 16).unwrap();
+    let environment = scene.environment().unwrap();
+    for normal in [
+        [0.0, 1.0, 0.0],
+        [0.0, -1.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 0.0, -1.0],
+    ] {
+        for c in environment.irradiance_at(normal).unwrap() {
+            assert!((c - std::f32::consts::PI).abs() < 0.05, "{normal:?} {c}");
+        }
+    }
+    assert_eq!(environment.specular(), Some(1));
+}
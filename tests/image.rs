@@ -0,0 +1,59 @@
+use rnm_3d::{Image, MipFilter};
+
+fn image(width: u32, height: u32) -> Image {
+    let pixels = (0..width * height * 4).map(|i| i as u8).collect::<Vec<_>>();
+    Image::from_raw(width, height, &pixels).unwrap()
+}
+
+#[test]
+fn mip_chain_sizes() {
+    for (width, height, sizes) in [
+        (16, 4, &[(16, 4), (8, 2), (4, 1), (2, 1), (1, 1)][..]),
+        (5, 3, &[(5, 3), (2, 1), (1, 1)]),
+        (1, 1, &[(1, 1)]),
+    ] {
+        let mut image = image(width, height);
+        image.generate_mips(MipFilter::Kaiser).unwrap();
+        assert_eq!(image.level_count(), sizes.len(), "{width}x{height}");
+        for (level, size) in sizes.iter().enumerate() {
+            assert_eq!(image.level_size(level), *size);
+            let pixels = image.decode_layer_rgba8(0, level).unwrap();
+            assert_eq!(pixels.len(), (size.0 * size.1 * 4) as usize);
+        }
+    }
+}
+
+#[test]
+fn cube_mips() {
+    let mut cube = Image::cube(std::array::from_fn(|_| image(8, 8))).unwrap();
+    cube.generate_mips(MipFilter::Box).unwrap();
+    assert_eq!(cube.level_count(), 4);
+    for face in 0..6 {
+        assert_eq!(cube.decode_layer_rgba8(face, 3).unwrap().len(), 4);
+    }
+    assert!(cube.layer_level(6, 0).is_none());
+}
+
+#[test]
+fn view_decode_into() {
+    let mut builder = rnm_3d::SceneBuilder::new();
+    builder.add_image(image(3, 2)).unwrap();
+    let rgb = Image::from_raw(2, 2, &[9; 12]).unwrap();
+    builder.add_image(rgb).unwrap();
+    let bytes = builder.build().to_bytes().unwrap();
+    let view = rnm_3d::SceneView::load(&bytes).unwrap();
+    let [rgba, rgb] = view.images() else {
+        panic!();
+    };
+    assert_eq!((rgba.channels(), rgb.channels()), (4, 3));
+    let mut buf = vec![0; rgb.decoded_len()];
+    assert_eq!(rgb.decode_into(&mut buf).unwrap(), 12);
+    assert_eq!(buf, [9; 12]);
+    let mut buf = vec![0; 16];
+    assert_eq!(rgb.decode_rgba8_into(&mut buf).unwrap(), 16);
+    assert_eq!(buf[..4], [9, 9, 9, 255]);
+    assert!(rgb.decode_into(&mut [0; 4]).is_err());
+    let mut buf = vec![0; rgba.decoded_len()];
+    rgba.decode_into(&mut buf).unwrap();
+    assert_eq!(buf, rgba.decode().unwrap());
+}
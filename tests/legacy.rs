@@ -0,0 +1,62 @@
+use rnm_3d::Scene;
+#[cfg(feature = "safe")]
+use rnm_3d::{ColorSpace, Light, LightUnit, SceneView, Section};
+
+// written by the v1 crate: a textured material on a mesh without uvs, a
+// second mesh with uvs and normals, one light of each v1 kind and two images
+const V1: &[u8] = include_bytes!("fixtures/v1.rnm");
+
+#[cfg(feature = "safe")]
+#[test]
+fn migrates() {
+    let scene = Scene::load(V1).unwrap();
+    assert!(scene.validate().is_empty());
+
+    let meshes = scene.meshes().unwrap();
+    assert_eq!(meshes.len(), 2);
+    assert_eq!(meshes[0].uvs(), None);
+    assert_eq!(meshes[0].material(), Some(0));
+    assert_eq!(meshes[1].uvs().map(<[_]>::len), Some(3));
+    assert_eq!(meshes[1].normals(), Some(&[[0.0, 0.0, 1.0]; 3][..]));
+
+    let materials = scene.materials().unwrap();
+    assert_eq!(materials[0].albedo().texture().map(|t| t.image()), Some(0));
+    assert_eq!(materials[0].normal_texture().map(|t| t.image()), Some(1));
+    assert_eq!(materials[0].metallic_roughness().roughness(), 0.2);
+    assert_eq!(materials[1].albedo().color(), &[1.0, 0.5, 0.25, 1.0]);
+
+    let lights = scene.lights().unwrap();
+    assert!(lights.iter().all(|l| l.unit() == LightUnit::Watt));
+    assert!(matches!(lights[0], Light::Point { power: 100.0, .. }));
+    assert!(matches!(lights[1], Light::Sun { power: 3.0, .. }));
+    let Light::Spot { angle, blend, .. } = lights[2] else {
+        panic!("{:?}", lights[2]);
+    };
+    assert_eq!((angle, blend), (std::f32::consts::FRAC_PI_8, 0.15));
+
+    // the normal map is data, the albedo stays sRGB
+    let images = scene.images().unwrap();
+    assert_eq!(images[0].color_space(), ColorSpace::Srgb);
+    assert_eq!(images[1].color_space(), ColorSpace::Linear);
+    assert_eq!(images[1].decode().unwrap(), [128, 128, 255]);
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn view_counts() {
+    let view = SceneView::load_sections(V1, &[Section::Lights]).unwrap();
+    assert_eq!(view.lights().len(), 3);
+    assert!(view.meshes().is_empty());
+    assert_eq!(view.count(Section::Meshes), 2);
+    assert_eq!(view.count(Section::Images), 2);
+    assert_eq!(view.compression(Section::Lights), None);
+}
+
+#[cfg(not(feature = "safe"))]
+#[test]
+fn needs_safe() {
+    assert!(matches!(
+        Scene::load(V1),
+        Err(rnm_3d::Error::LegacyRequiresSafe)
+    ));
+}
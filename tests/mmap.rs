@@ -0,0 +1,19 @@
+#![cfg(feature = "mmap")]
+
+use rnm_3d::{Compression, Image, SaveOptions, SceneBuilder, SceneView, Section};
+
+#[test]
+fn open_mmap() {
+    let mut builder = SceneBuilder::new();
+    builder
+        .add_image(Image::from_raw(2, 2, &[7; 16]).unwrap())
+        .unwrap();
+    let path = std::env::temp_dir().join(format!("rnm-3d-mmap-{}.rnm", std::process::id()));
+    let options = SaveOptions::new().with_section(Section::Images, Compression::None);
+    builder.build().save_with(&path, &options).unwrap();
+    let view = SceneView::open_mmap(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(view.is_mapped(Section::Images));
+    assert_eq!(view.images()[0].decode_rgba8().unwrap(), [7; 16]);
+    assert!(SceneView::open_mmap(&path).is_err());
+}
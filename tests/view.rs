@@ -0,0 +1,82 @@
+#[cfg(feature = "safe")]
+use rnm_3d::{Error, ValidationError};
+use rnm_3d::{Image, MaterialBuilder, MeshBuilder, SceneBuilder, SceneView, Section};
+
+// two images, a material using the second one and a mesh using the material
+fn bytes() -> Vec<u8> {
+    let mut builder = SceneBuilder::new();
+    let white = || Image::from_raw(2, 2, &[255; 16]).unwrap();
+    builder.add_image(white()).unwrap();
+    let image = builder.add_image(white()).unwrap();
+    let material = builder
+        .add_material(MaterialBuilder::new().albedo_texture(image))
+        .unwrap();
+    builder
+        .add_mesh(MeshBuilder::new(vec![[0.0; 3]; 3]).material(material))
+        .unwrap();
+    builder.build().to_bytes().unwrap()
+}
+
+// rewrites the item count of `section` in the table of contents
+#[cfg(feature = "safe")]
+fn set_count(bytes: &mut [u8], section: Section, count: u32) {
+    let sections = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+    let toc = 16..16 + sections * 40;
+    let entry = bytes[toc.clone()]
+        .chunks_exact(40)
+        .position(|e| e[..4] == (section as u32).to_le_bytes())
+        .unwrap();
+    let at = toc.start + entry * 40 + 36;
+    bytes[at..at + 4].copy_from_slice(&count.to_le_bytes());
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&bytes[..12]);
+    hasher.update(&bytes[toc]);
+    let checksum = hasher.finalize();
+    bytes[12..16].copy_from_slice(&checksum.to_le_bytes());
+}
+
+#[test]
+fn only_requested_sections() {
+    let view = SceneView::load_sections(&bytes(), &[Section::Materials]).unwrap();
+    assert_eq!(view.materials().len(), 1);
+    assert!(view.meshes().is_empty());
+    assert!(view.images().is_empty());
+    assert_eq!(view.count(Section::Materials), 1);
+    assert_eq!(view.count(Section::Meshes), 1);
+    assert_eq!(view.count(Section::Images), 2);
+    assert_eq!(view.count(Section::Lights), 0);
+    assert!(view.environment().is_none());
+    assert_eq!(view.material_images(&[0]), [1]);
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn references_use_toc_counts() {
+    let mut bytes = bytes();
+    set_count(&mut bytes, Section::Images, 1);
+    // the images aren't loaded, the count alone makes image 1 out of range
+    let err = SceneView::load_sections(&bytes, &[Section::Materials]).unwrap_err();
+    let Error::Invalid(errors) = err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(
+        errors,
+        [ValidationError::ImageOutOfRange {
+            material: 0,
+            image: 1,
+            images: 1
+        }]
+    );
+    // loading the images as well also catches the count not matching them
+    let err = SceneView::load_sections(&bytes, &[Section::Images]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Invalid(e) if e == [ValidationError::SectionCount {
+            section: Section::Images,
+            expected: 1,
+            len: 2
+        }]
+    ));
+    // meshes point at materials, which still match
+    assert!(SceneView::load_sections(&bytes, &[Section::Meshes]).is_ok());
+}